@@ -1,11 +1,12 @@
-use anyhow::Result;
 use std::path::Path;
 use std::time::SystemTime;
 
+use tiler_core::catalog::puzzle_order;
+use tiler_core::difficulty;
+use tiler_core::lint::{lint, LintError};
+use tiler_core::puzzles::{PuzzleDefinition, PuzzleFormat};
 use walkdir::WalkDir;
 
-include!("src/puzzles/mod.rs");
-
 fn modified_time(direntry: &walkdir::DirEntry) -> SystemTime {
     direntry
         .metadata()
@@ -14,7 +15,11 @@ fn modified_time(direntry: &walkdir::DirEntry) -> SystemTime {
         .unwrap_or(SystemTime::UNIX_EPOCH)
 }
 
-fn puzzle_and_short_name(path: &Path) -> Result<(PuzzleDefinition, String)> {
+/// Set to `true` to list recently edited puzzles first in the level select
+const RECENT_FIRST_VAR: &str = "TILER_RECENT_FIRST";
+
+/// Loads and lints a puzzle file, returning `None` if the file isn't a puzzle
+fn puzzle_and_short_name(path: &Path) -> Option<Result<(PuzzleDefinition, String), LintError>> {
     let full_shortname = path
         .as_os_str()
         .to_owned()
@@ -24,60 +29,105 @@ fn puzzle_and_short_name(path: &Path) -> Result<(PuzzleDefinition, String)> {
         .unwrap()
         .to_string();
 
-    let fcontents = std::fs::read_to_string(path)?;
+    let (shortname, format) = PuzzleFormat::from_file_name(&full_shortname)?;
 
-    if let Some(shortname) = full_shortname.strip_suffix(".yaml") {
-        Ok((serde_yaml::from_str(&fcontents)?, shortname.to_string()))
-    } else if let Some(shortname) = full_shortname.strip_suffix(".txt") {
-        Ok((
-            PuzzleDefinition::from_ascii_art(shortname.to_string(), fcontents),
-            shortname.to_string(),
-        ))
-    } else {
-        Err(anyhow::anyhow!("not a file or unknown extension"))
-    }
+    let fcontents = std::fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("failed to read {}: {err}", path.display()));
+
+    Some(
+        format
+            .parse(shortname, fcontents)
+            .map_err(LintError::Parse)
+            .and_then(|puzzle| {
+                lint(&puzzle)?;
+                Ok((puzzle, shortname.to_string()))
+            }),
+    )
 }
 
 fn main() {
     let out_dir = std::env::var("OUT_DIR").unwrap();
     let dest_path = Path::new(&out_dir).join("puzzle_definitions.rs");
 
-    // If we're in CI, default sorting is good
-    // If we're running a local build (i.e. not in CI), sort puzzles by
-    // mtime order to speed up iteration cycles.
-    let files = if std::env::var("CI").unwrap_or("false".to_string()) == "true" {
-        WalkDir::new("src/puzzles")
-    } else {
-        WalkDir::new("src/puzzles").sort_by(|d1, d2| modified_time(d2).cmp(&modified_time(d1)))
-    };
+    // Watching the env var stops cargo rerunning this on every change, so watch the puzzles too
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/puzzles");
+    println!("cargo:rerun-if-env-changed={RECENT_FIRST_VAR}");
 
-    let mut puzzles = files
+    let mut puzzles = vec![];
+    let mut modified_times = vec![];
+    let mut errors = vec![];
+    for file in WalkDir::new("src/puzzles")
         .into_iter()
         .filter_map(|d| d.ok())
         .filter(|direntry| direntry.file_type().is_file())
-        .filter_map(|file| puzzle_and_short_name(file.path()).ok())
-        .collect::<Vec<(PuzzleDefinition, String)>>();
-
-    if std::env::var("CI").unwrap_or("false".to_string()) == "true" {
-        puzzles.sort_by_cached_key(|(_, shortname)| shortname.clone());
+    {
+        match puzzle_and_short_name(file.path()) {
+            Some(Ok((puzzle, shortname))) => {
+                modified_times.push((shortname.clone(), modified_time(&file)));
+                puzzles.push((puzzle, shortname));
+            }
+            Some(Err(err)) => errors.push(match err.line() {
+                Some(line) => format!("{}:{line}: {err}", file.path().display()),
+                None => format!("{}: {err}", file.path().display()),
+            }),
+            None => {}
+        }
     }
 
-    let mut puzzle_map = phf_codegen::Map::new();
-    for (idx, (_, shortname)) in puzzles.iter().enumerate() {
-        puzzle_map.entry(shortname, &format!("{idx}"));
+    if !errors.is_empty() {
+        for error in &errors {
+            eprintln!("error: {error}");
+        }
+        panic!("{} puzzle(s) failed to validate", errors.len());
     }
 
+    puzzles.sort_by(|(puzzle1, shortname1), (puzzle2, shortname2)| {
+        puzzle_order((shortname1, puzzle1), (shortname2, puzzle2))
+    });
+
+    // Rated here rather than at startup, since rating solves every puzzle
+    let difficulties = puzzles
+        .iter()
+        .map(|(puzzle, _)| difficulty::rate(puzzle).0)
+        .collect::<Vec<u32>>();
+
+    // Developers can list the puzzles they've edited most recently first, to speed up
+    // iteration cycles. This is kept apart from the order above, so indices don't change.
+    let recently_edited = if std::env::var(RECENT_FIRST_VAR).unwrap_or_default() == "true" {
+        modified_times.sort_by(|(_, time1), (_, time2)| time2.cmp(time1));
+        modified_times
+            .iter()
+            .filter_map(|(shortname, _)| {
+                puzzles
+                    .iter()
+                    .position(|(_, other_shortname)| other_shortname == shortname)
+            })
+            .collect::<Vec<usize>>()
+    } else {
+        vec![]
+    };
+
     std::fs::write(
         &dest_path,
         format!(
-            "pub const PUZZLES: [&'static str; {}] = [\n{}\n];\npub static PUZZLE_NAME_MAP: phf::Map<&'static str, usize> = {};",
+            "pub const PUZZLES: [&'static str; {}] = [\n{}\n];\npub const PUZZLE_SHORT_NAMES: [&'static str; {}] = [{}];\npub const PUZZLE_DIFFICULTIES: [u32; {}] = {:?};\npub const RECENTLY_EDITED: [usize; {}] = {:?};",
             puzzles.len(),
             puzzles
                 .iter()
-                .map(|(puzzle, _)| format!("\"{}\"", serde_yaml::to_string(puzzle).unwrap()))
+                .map(|(puzzle, _)| format!("{:?}", serde_yaml::to_string(puzzle).unwrap()))
                 .collect::<Vec<_>>()
                 .join(",\n"),
-            puzzle_map.build()
+            puzzles.len(),
+            puzzles
+                .iter()
+                .map(|(_, shortname)| format!("{shortname:?}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            difficulties.len(),
+            difficulties,
+            recently_edited.len(),
+            recently_edited
         ),
     )
     .unwrap();
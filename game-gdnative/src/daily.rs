@@ -0,0 +1,20 @@
+use gdnative::api::OS;
+
+pub use tiler_core::daily::{puzzle, share_result, Date};
+
+/// Daily puzzles are saved and shared under this, followed by their date
+const SHORTNAME_PREFIX: &str = "daily/";
+
+/// Returns today's date in UTC, so every player is on the same puzzle at the same time
+pub fn today() -> Date {
+    Date::from_unix_time(OS::godot_singleton().get_unix_time())
+}
+
+pub fn shortname(date: Date) -> String {
+    format!("{SHORTNAME_PREFIX}{date}")
+}
+
+/// Returns the date of a daily puzzle's short name, or `None` if it isn't one
+pub fn date(shortname: &str) -> Option<Date> {
+    Date::parse(shortname.strip_prefix(SHORTNAME_PREFIX)?)
+}
@@ -0,0 +1,341 @@
+use gdnative::{
+    api::{
+        CheckBox, GlobalConstants, HBoxContainer, InputEventMouseButton, InputEventMouseMotion,
+        JavaScript, Label, VBoxContainer, OS,
+    },
+    prelude::*,
+};
+
+use crate::tile::{TILE_INNER_OFFSET, TILE_INNER_SIDE_LEN, TILE_SIDE_LEN};
+use crate::util;
+use tiler_core::design::{Design, Paint};
+use tiler_core::puzzles::Position;
+use tiler_core::render;
+
+/// How many cells the design grid has across and down
+const GRID_COLUMNS: usize = 15;
+const GRID_ROWS: usize = 12;
+/// Where the design grid's top left corner is, leaving room for the tools above it
+const GRID_OFFSET: Vector2 = Vector2 { x: 25.0, y: 80.0 };
+
+const EMPTY_COLOR: Color = Color {
+    r: 0.1f32,
+    g: 0.1f32,
+    b: 0.1f32,
+    a: 1.0f32,
+};
+const BOARD_COLOR: Color = Color {
+    r: 0.2f32,
+    g: 0.2f32,
+    b: 0.2f32,
+    a: 1.0f32,
+};
+const FIXED_COLOR: Color = Color {
+    r: 0.3f32,
+    g: 0.3f32,
+    b: 0.3f32,
+    a: 1.0f32,
+};
+
+/// The brushes picked by the tool buttons
+const BRUSH_BOARD: i64 = 0;
+const BRUSH_FIXED: i64 = 1;
+const BRUSH_NEW_PIECE: i64 = 2;
+
+/// Lets players draw their own puzzles. Cells are painted as board, fixed or as part of a piece,
+/// with every piece painted where it goes in the solution. The design is checked after every
+/// stroke, and can be exported in either puzzle file format.
+#[derive(NativeClass)]
+#[inherit(Node2D)]
+#[no_constructor]
+pub struct Editor {
+    design: Design,
+    brush: Paint,
+    /// Whether the mouse is painting (`Some(true)`) or erasing (`Some(false)`) as it moves
+    stroke: Option<bool>,
+    status: Option<Ref<Label>>,
+}
+
+#[methods]
+impl Editor {
+    #[method]
+    fn _ready(&mut self, #[base] base: TRef<Node2D>) {
+        let brushes = [
+            ("Board", BRUSH_BOARD),
+            ("Fixed", BRUSH_FIXED),
+            ("New Piece", BRUSH_NEW_PIECE),
+        ];
+        let exports = [("Export YAML", "yaml"), ("Export ASCII", "txt")];
+
+        let tools = HBoxContainer::new();
+        for (text, brush) in brushes {
+            tools.add_child(
+                button(base, text, "_on_brush_pressed", brush.to_variant()),
+                false,
+            );
+        }
+        tools.add_child(check_box(base, "Rotation", "_on_rotation_toggled"), false);
+        tools.add_child(check_box(base, "Flip", "_on_flip_toggled"), false);
+        for (text, extension) in exports {
+            tools.add_child(
+                button(base, text, "_on_export_pressed", extension.to_variant()),
+                false,
+            );
+        }
+
+        let status = Label::new().into_shared();
+
+        let layout = VBoxContainer::new();
+        layout.set_position(Vector2::new(GRID_OFFSET.x, 10.0), false);
+        layout.add_child(tools, false);
+        layout.add_child(status, false);
+        base.add_child(layout, false);
+
+        self.status = Some(status);
+        self.update_status();
+    }
+
+    #[method]
+    fn _draw(&self, #[base] base: &Node2D) {
+        let colors = piece_colors(self.design.new_piece_id());
+
+        for (x, y) in itertools::iproduct!(0..GRID_COLUMNS, 0..GRID_ROWS) {
+            let color = match self.design.get(Position(x, y)) {
+                None => EMPTY_COLOR,
+                Some(Paint::Board) => BOARD_COLOR,
+                Some(Paint::Fixed) => FIXED_COLOR,
+                Some(Paint::Piece(id)) => colors[id],
+            };
+
+            let corner = GRID_OFFSET + util::position_to_vector(Position(x, y)) * TILE_SIDE_LEN;
+            base.draw_rect(
+                Rect2::new(
+                    corner + TILE_INNER_OFFSET,
+                    Vector2::new(TILE_INNER_SIDE_LEN, TILE_INNER_SIDE_LEN),
+                ),
+                color,
+                true,
+                1.0,
+                false,
+            );
+        }
+    }
+
+    #[method]
+    fn _unhandled_input(&mut self, #[base] base: &Node2D, event: Ref<InputEvent>) {
+        let event = unsafe { event.assume_safe() };
+
+        if let Some(event) = event.cast::<InputEventMouseButton>() {
+            if !event.is_pressed() {
+                if self.stroke.take().is_some() {
+                    self.update_status();
+                }
+                return;
+            }
+
+            let cell = match grid_cell(event.position()) {
+                Some(cell) => cell,
+                None => return,
+            };
+
+            match event.button_index() {
+                // Shift-click picks up the brush a cell was painted with
+                GlobalConstants::BUTTON_LEFT if event.shift() => {
+                    if let Some(paint) = self.design.get(cell) {
+                        self.brush = paint;
+                        self.update_status();
+                    }
+                }
+                GlobalConstants::BUTTON_LEFT => self.stroke = Some(true),
+                GlobalConstants::BUTTON_RIGHT => self.stroke = Some(false),
+                _ => return,
+            }
+
+            self.apply_stroke(base, cell);
+        } else if let Some(event) = event.cast::<InputEventMouseMotion>() {
+            if let Some(cell) = grid_cell(event.position()) {
+                self.apply_stroke(base, cell);
+            }
+        }
+    }
+
+    #[method]
+    fn _on_brush_pressed(&mut self, brush: i64) {
+        self.brush = match brush {
+            BRUSH_BOARD => Paint::Board,
+            BRUSH_FIXED => Paint::Fixed,
+            BRUSH_NEW_PIECE => Paint::Piece(self.design.new_piece_id()),
+            _ => return,
+        };
+        self.update_status();
+    }
+
+    #[method]
+    fn _on_rotation_toggled(&mut self, pressed: bool) {
+        self.design.allow_rotation = pressed;
+        self.update_status();
+    }
+
+    #[method]
+    fn _on_flip_toggled(&mut self, pressed: bool) {
+        self.design.allow_flip = pressed;
+        self.update_status();
+    }
+
+    #[method]
+    fn _on_export_pressed(&self, extension: String) {
+        let contents = if extension == "txt" {
+            self.design.to_ascii_art()
+        } else {
+            self.design
+                .to_definition()
+                .map(|definition| serde_yaml::to_string(&definition).unwrap())
+        };
+
+        let contents = match contents {
+            Some(contents) => contents,
+            None => {
+                self.set_status(&if extension == "txt" {
+                    format!(
+                        "ASCII art needs every cell covered by one of up to {} pieces",
+                        render::MAX_PIECE_LABELS
+                    )
+                } else {
+                    "Paint some cells before exporting".to_string()
+                });
+                return;
+            }
+        };
+
+        OS::godot_singleton().set_clipboard(contents.as_str());
+
+        let file_name = format!("design.{extension}");
+        if util::is_web() {
+            download(&file_name, &contents);
+            self.set_status(&format!("Copied and downloaded {file_name}"));
+        } else {
+            util::write_file(&format!("user://{file_name}"), &contents);
+            self.set_status(&format!("Copied and saved to user://{file_name}"));
+        }
+    }
+}
+
+impl Editor {
+    pub fn new_instance() -> Instance<Self, Unique> {
+        Self {
+            design: Design::new("My Puzzle".to_string()),
+            brush: Paint::Board,
+            stroke: None,
+            status: None,
+        }
+        .emplace()
+    }
+
+    fn apply_stroke(&mut self, base: &Node2D, cell: Position) {
+        let changed = match self.stroke {
+            Some(true) if self.design.get(cell) != Some(self.brush) => {
+                self.design.paint(cell, self.brush);
+                true
+            }
+            Some(false) if self.design.get(cell).is_some() => {
+                self.design.erase(cell);
+                true
+            }
+            _ => false,
+        };
+
+        if changed {
+            base.update();
+        }
+    }
+
+    /// Shows the brush and whether the design is ready to be a puzzle
+    fn update_status(&self) {
+        let brush = match self.brush {
+            Paint::Board => "board".to_string(),
+            Paint::Fixed => "fixed".to_string(),
+            Paint::Piece(id) => format!("piece {}", id + 1),
+        };
+        let check = match self.design.check() {
+            None => "paint some cells to start".to_string(),
+            Some(Ok(())) => "solvable with a unique solution".to_string(),
+            Some(Err(err)) => err.to_string(),
+        };
+
+        self.set_status(&format!("Brush: {brush} | {check}"));
+    }
+
+    fn set_status(&self, text: &str) {
+        if let Some(status) = self.status {
+            unsafe { status.assume_safe() }.set_text(text);
+        }
+    }
+}
+
+/// Returns the design cell under a point on the screen, if there is one
+fn grid_cell(point: Vector2) -> Option<Position> {
+    let cell = ((point - GRID_OFFSET) / TILE_SIDE_LEN).floor();
+    if cell.x < 0.0 || cell.y < 0.0 {
+        return None;
+    }
+
+    let (x, y) = (cell.x as usize, cell.y as usize);
+    (x < GRID_COLUMNS && y < GRID_ROWS).then_some(Position(x, y))
+}
+
+/// Returns a color for each of `count` pieces
+fn piece_colors(count: usize) -> Vec<Color> {
+    colorgrad::warm()
+        .colors(count)
+        .iter()
+        .map(|color| Color {
+            r: color.r as f32,
+            g: color.g as f32,
+            b: color.b as f32,
+            a: color.a as f32,
+        })
+        .collect()
+}
+
+fn button(base: TRef<Node2D>, text: &str, callback: &str, bind: Variant) -> Ref<Button, Unique> {
+    let button = Button::new();
+    button.set_text(text);
+    button
+        .connect(
+            "pressed",
+            base,
+            callback,
+            VariantArray::from_iter([bind]).into_shared(),
+            0,
+        )
+        .unwrap_or_else(|_| panic!("Failed to connect to pressed signal on {text}"));
+
+    button
+}
+
+fn check_box(base: TRef<Node2D>, text: &str, callback: &str) -> Ref<CheckBox, Unique> {
+    let check_box = CheckBox::new();
+    check_box.set_text(text);
+    check_box
+        .connect("toggled", base, callback, VariantArray::new_shared(), 0)
+        .unwrap_or_else(|_| panic!("Failed to connect to toggled signal on {text}"));
+
+    check_box
+}
+
+/// Has the browser download a text file
+fn download(file_name: &str, contents: &str) {
+    JavaScript::godot_singleton().eval(
+        format!(
+            "(function() {{
+                const link = document.createElement('a');
+                link.href = URL.createObjectURL(new Blob([{}], {{ type: 'text/plain' }}));
+                link.download = {};
+                link.click();
+            }})()",
+            util::js_string(contents),
+            util::js_string(file_name)
+        ),
+        true,
+    );
+}
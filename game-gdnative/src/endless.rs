@@ -0,0 +1,47 @@
+use tiler_core::generator::{self, GeneratorOptions};
+use tiler_core::puzzles::PuzzleDefinition;
+
+/// Endless puzzles are saved and shared under this, followed by their seed
+const SHORTNAME_PREFIX: &str = "endless/";
+
+const BOARD_WIDTH: usize = 6;
+const BOARD_HEIGHT: usize = 5;
+
+/// Returns a seed for a puzzle the player probably hasn't seen. Seeds are kept short so
+/// shared links stay readable.
+pub fn new_seed() -> u64 {
+    rand::random::<u32>() as u64
+}
+
+pub fn shortname(seed: u64) -> String {
+    format!("{SHORTNAME_PREFIX}{seed}")
+}
+
+/// Returns the seed of an endless puzzle's short name, or `None` if it isn't one
+pub fn seed(shortname: &str) -> Option<u64> {
+    shortname.strip_prefix(SHORTNAME_PREFIX)?.parse().ok()
+}
+
+/// Generates the endless puzzle for a seed. These should have a unique solution, but rather than
+/// having nothing to play, the first cut of the board is used if none of them do.
+pub fn puzzle(seed: u64) -> PuzzleDefinition {
+    let options = GeneratorOptions {
+        unique_solution: true,
+        ..GeneratorOptions::rect(BOARD_WIDTH, BOARD_HEIGHT)
+    };
+
+    let mut puzzle = generator::generate(seed, &options)
+        .or_else(|_| {
+            generator::generate(
+                seed,
+                &GeneratorOptions {
+                    unique_solution: false,
+                    ..options
+                },
+            )
+        })
+        .expect("Failed to cut the board into pieces");
+    puzzle.name = format!("Endless #{seed}");
+
+    puzzle
+}
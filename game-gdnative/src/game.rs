@@ -1,10 +1,21 @@
 use gdnative::{
-    api::{AcceptDialog, JavaScript, MenuButton},
+    api::{AcceptDialog, CheckBox, JavaScript, Label, OptionButton, PopupPanel, VBoxContainer, OS},
     prelude::*,
 };
 
+use crate::daily;
+use crate::editor::Editor;
+use crate::endless;
+use crate::packs::{CATALOG, RECENTLY_EDITED};
 use crate::puzzle::Puzzle;
-include!(concat!(env!("OUT_DIR"), "/puzzle_definitions.rs"));
+use crate::save;
+use crate::tray::Overlay;
+use tiler_core::board::ValidationReport;
+use tiler_core::chapters;
+use tiler_core::daily::format_time;
+use tiler_core::hints::Hint;
+use tiler_core::palette::Palette;
+use tiler_core::save::Settings;
 
 use super::util;
 
@@ -12,88 +23,414 @@ use super::util;
 #[inherit(Node2D)]
 pub struct Main {
     puzzle_node: Option<Instance<Puzzle>>,
+    editor_node: Option<Instance<Editor>>,
     alert: Option<Ref<AcceptDialog>>,
+    /// Shown once a puzzle is solved
+    completion: Option<Ref<PopupPanel>>,
+    /// The player's settings, which every puzzle shown follows
+    settings: Settings,
+}
+
+const COMPLETION_SIZE: Vector2 = Vector2 { x: 300.0, y: 0.0 };
+
+/// The controls along the bottom of the window in `UI.tscn`
+const BUTTONS: &[&str] = &[
+    "LevelSelectButton",
+    "ValidatePuzzleButton",
+    "UndoButton",
+    "RedoButton",
+    "ResetButton",
+    "ShareButton",
+    "EditorButton",
+    "EndlessButton",
+    "DailyButton",
+    "HintButton",
+    "AutoValidateCheckBox",
+];
+/// The controls in the top right corner of the window in `UI.tscn`
+const SETTINGS: &[&str] = &["PaletteButton", "GlyphsCheckBox"];
+
+/// What the completion screen's next puzzle button starts
+#[derive(Debug, Copy, Clone)]
+enum NextPuzzle {
+    Catalog(usize),
+    Endless,
 }
 
 #[methods]
 impl Main {
     #[method]
     fn _ready(&mut self, #[base] base: TRef<Node2D>) {
+        self.settings = save::load().settings;
+
         // Register the UI layer
         let ui = self.get_ui(base);
         self.register_puzzle_select_callback(base, ui, "_on_puzzle_selected");
-        self.register_validate_callback(base, ui, "_on_validate_requested");
+        self.register_button_callback(base, ui, "ValidatePuzzleButton", "_on_validate_requested");
+        self.register_button_callback(base, ui, "UndoButton", "_on_undo_requested");
+        self.register_button_callback(base, ui, "RedoButton", "_on_redo_requested");
+        self.register_button_callback(base, ui, "ResetButton", "_on_reset_requested");
+        self.register_button_callback(base, ui, "ShareButton", "_on_share_requested");
+        self.register_button_callback(base, ui, "EditorButton", "_on_editor_requested");
+        self.register_button_callback(base, ui, "EndlessButton", "_on_endless_requested");
+        self.register_button_callback(base, ui, "DailyButton", "_on_daily_requested");
+        self.register_button_callback(base, ui, "HintButton", "_on_hint_requested");
 
-        let init_puzzle_idx = JavaScript::godot_singleton()
-            .eval(
-                "(new URLSearchParams(window.location.search)).get('puzzle')",
-                true,
-            )
-            .to::<String>()
-            .and_then(|shortname| {
-                shortname
-                    .strip_suffix("/")
-                    .map(str::to_string)
-                    .or(Some(shortname))
-            })
-            .and_then(|shortname| PUZZLE_NAME_MAP.get(&shortname))
-            .copied()
+        self.register_check_box_callback(
+            base,
+            ui,
+            "AutoValidateCheckBox",
+            self.settings.auto_validate,
+            "_on_auto_validate_toggled",
+        );
+        self.register_check_box_callback(
+            base,
+            ui,
+            "GlyphsCheckBox",
+            self.settings.glyphs,
+            "_on_glyphs_toggled",
+        );
+
+        let palette_button = get_ui_control::<OptionButton>(ui, "PaletteButton");
+        for (idx, palette) in Palette::ALL.iter().enumerate() {
+            palette_button.add_item(palette.name(), idx as i64);
+        }
+        let selected = Palette::ALL
+            .iter()
+            .position(|palette| *palette == self.settings.palette)
             .unwrap_or(0);
+        palette_button.select(selected as i64);
+        palette_button
+            .connect(
+                "item_selected",
+                base,
+                "_on_palette_selected",
+                VariantArray::new_shared(),
+                0,
+            )
+            .expect("Failed to connect to item_selected signal on PaletteButton");
+
+        let shortname = util::url_param("puzzle").map(|shortname| {
+            shortname
+                .strip_suffix('/')
+                .map(str::to_string)
+                .unwrap_or(shortname)
+        });
+
+        if let Some(seed) = shortname.as_deref().and_then(endless::seed) {
+            self.show_endless(base, seed);
+        } else if let Some(date) = shortname.as_deref().and_then(daily::date) {
+            self.show_daily(base, date);
+        } else {
+            let init_puzzle_idx = shortname
+                .and_then(|shortname| CATALOG.index_of(&shortname))
+                .or_else(|| RECENTLY_EDITED.first().copied())
+                .unwrap_or(0);
+
+            self._on_puzzle_selected(base, init_puzzle_idx);
+        }
 
-        self._on_puzzle_selected(base.as_ref(), init_puzzle_idx);
+        if let (Some(state), Some(puzzle)) = (util::url_param("state"), self.puzzle_node.as_ref()) {
+            unsafe { puzzle.assume_safe() }
+                .map_mut(|puzzle, puzzle_node| puzzle.load_shared_state(puzzle_node, &state))
+                .expect("Failed to load shared board state");
+        }
 
         let alert = AcceptDialog::new();
         let alert = alert.into_shared();
         base.add_child(alert, false);
         self.alert = Some(alert);
+
+        let completion = PopupPanel::new().into_shared();
+        base.add_child(completion, false);
+        self.completion = Some(completion);
     }
 
     #[method]
-    fn _on_puzzle_selected(&mut self, #[base] base: &Node2D, puzzle_idx: usize) {
+    fn _on_puzzle_selected(&mut self, #[base] base: TRef<Node2D>, puzzle_idx: usize) {
         godot_print!("puzzle selected: {}", puzzle_idx);
 
-        if let Some(puzzle) = self.puzzle_node.take() {
-            base.remove_child(puzzle);
-        }
+        self.show_puzzle(base, Puzzle::from_idx(puzzle_idx, self.overlay(base)));
+    }
 
-        let puzzle = Puzzle::from_idx(puzzle_idx).into_shared();
-        self.puzzle_node = Some(puzzle.clone());
+    /// Starts a freshly generated puzzle
+    #[method]
+    fn _on_endless_requested(&mut self, #[base] base: TRef<Node2D>) {
+        self.show_endless(base, endless::new_seed());
+    }
 
-        unsafe {
-            puzzle
-                .assume_safe()
-                .base()
-                .set_global_position(util::screen_center(base.upcast::<Node>()))
-        }
+    /// Starts today's puzzle
+    #[method]
+    fn _on_daily_requested(&mut self, #[base] base: TRef<Node2D>) {
+        self.show_daily(base, daily::today());
+    }
 
-        base.add_child(puzzle, false);
+    /// Swaps the puzzle for a blank editor
+    #[method]
+    fn _on_editor_requested(&mut self, #[base] base: &Node2D) {
+        self.clear(base);
+
+        let editor = Editor::new_instance().into_shared();
+        self.editor_node = Some(editor.clone());
+        base.add_child(editor, false);
     }
 
+    /// Checks the board. A valid solution makes the puzzle emit `puzzle_solved`, which brings up
+    /// the completion screen, so only problems are shown here.
     #[method]
     fn _on_validate_requested(&self) {
-        let valid = unsafe { self.puzzle_node.as_ref().unwrap().assume_safe() }
-            .map(Puzzle::validate)
-            .unwrap_or(false);
+        let puzzle = match self.puzzle_node.as_ref() {
+            Some(puzzle) => puzzle,
+            None => return,
+        };
+        let report = unsafe { puzzle.assume_safe() }
+            .map_mut(Puzzle::validate)
+            .ok();
 
         let alert = unsafe { self.alert.unwrap().assume_safe() };
 
-        if valid {
-            alert.set_title("Congratulations!");
-            alert.set_text("Your solution is valid.");
-        } else {
-            alert.set_title("Uh oh!");
-            alert.set_text("There's an issue with your solution :(");
+        match report {
+            Some(report) if report.is_valid() => return,
+            Some(report) => {
+                alert.set_title("Uh oh!");
+                alert.set_text(describe_problems(&report));
+            }
+            None => {
+                alert.set_title("Uh oh!");
+                alert.set_text("There's an issue with your solution :(");
+            }
+        }
+
+        alert.popup_centered_minsize(Vector2::ZERO);
+    }
+
+    /// Shows how the attempt went, with a way on to the next puzzle
+    #[method]
+    fn _on_puzzle_solved(&self, #[base] base: TRef<Node2D>, elapsed: f64, moves: i64) {
+        let puzzle = match self.puzzle_node.as_ref() {
+            Some(puzzle) => puzzle,
+            None => return,
+        };
+        let (shortname, hints) = unsafe { puzzle.assume_safe() }
+            .map(|puzzle, _| (puzzle.shortname().to_string(), puzzle.hints()))
+            .expect("Failed to read the solved puzzle");
+
+        let summary = VBoxContainer::new();
+        summary.set_custom_minimum_size(COMPLETION_SIZE);
+        summary.add_child(centered_label("Puzzle solved!"), false);
+        summary.add_child(
+            centered_label(&format!("Time: {}", format_time(elapsed))),
+            false,
+        );
+        summary.add_child(centered_label(&format!("Moves: {moves}")), false);
+        if hints > 0 {
+            summary.add_child(centered_label(&format!("Hints: {hints}")), false);
         }
 
+        // Daily puzzles come with a result to share, like word games
+        if let Some(date) = daily::date(&shortname) {
+            let result = daily::share_result(date, elapsed, moves as usize, hints);
+            OS::godot_singleton().set_clipboard(result.as_str());
+            summary.add_child(centered_label("Copied your result to share."), false);
+        }
+
+        if self.next_puzzle().is_some() {
+            summary.add_child(
+                popup_button(base, "Next Puzzle", "_on_next_puzzle_requested"),
+                false,
+            );
+        }
+        summary.add_child(popup_button(base, "Close", "_on_completion_closed"), false);
+
+        let completion = unsafe { self.completion.unwrap().assume_safe() };
+        for child in completion.get_children().iter() {
+            if let Some(child) = child.to_object::<Node>() {
+                unsafe { child.assume_safe() }.queue_free();
+            }
+        }
+        completion.add_child(summary, false);
+        completion.popup_centered(COMPLETION_SIZE);
+    }
+
+    #[method]
+    fn _on_next_puzzle_requested(&mut self, #[base] base: TRef<Node2D>) {
+        self._on_completion_closed();
+
+        match self.next_puzzle() {
+            Some(NextPuzzle::Catalog(puzzle_idx)) => self._on_puzzle_selected(base, puzzle_idx),
+            Some(NextPuzzle::Endless) => self._on_endless_requested(base),
+            None => {}
+        }
+    }
+
+    #[method]
+    fn _on_completion_closed(&self) {
+        unsafe { self.completion.unwrap().assume_safe() }.hide();
+    }
+
+    #[method]
+    fn _on_auto_validate_toggled(&mut self, pressed: bool) {
+        self.update_settings(|settings| settings.auto_validate = pressed);
+    }
+
+    #[method]
+    fn _on_glyphs_toggled(&mut self, pressed: bool) {
+        self.update_settings(|settings| settings.glyphs = pressed);
+    }
+
+    #[method]
+    fn _on_palette_selected(&mut self, index: i64) {
+        if let Some(palette) = Palette::ALL.get(index as usize) {
+            self.update_settings(|settings| settings.palette = *palette);
+        }
+    }
+
+    #[method]
+    fn _on_hint_requested(&self) {
+        let hint = match self.puzzle_node.as_ref() {
+            Some(puzzle) => unsafe { puzzle.assume_safe() }
+                .map_mut(|puzzle, _| puzzle.hint())
+                .expect("Failed to get a hint"),
+            None => return,
+        };
+
+        let text = match hint {
+            // The highlighted spot speaks for itself
+            Hint::Place { .. } => return,
+            Hint::Misplaced(_) => "The highlighted piece can't stay where it is.",
+            Hint::Solved => "The puzzle is already solved!",
+            Hint::Unsolvable => "This puzzle has no solution, so there's no hint to give.",
+        };
+
+        let alert = unsafe { self.alert.unwrap().assume_safe() };
+        alert.set_title("Hint");
+        alert.set_text(text);
+        alert.popup_centered_minsize(Vector2::ZERO);
+    }
+
+    #[method]
+    fn _on_share_requested(&self) {
+        let query = match self.puzzle_node.as_ref() {
+            Some(puzzle) => unsafe { puzzle.assume_safe() }
+                .map(Puzzle::share_query)
+                .expect("Failed to get the puzzle's board state"),
+            None => return,
+        };
+
+        // Web builds link straight to the page. Elsewhere there's no page, so just the query.
+        let url = JavaScript::godot_singleton()
+            .eval("window.location.origin + window.location.pathname", true)
+            .to::<String>()
+            .map(|page| page + &query)
+            .unwrap_or(query);
+        OS::godot_singleton().set_clipboard(url.as_str());
+
+        let alert = unsafe { self.alert.unwrap().assume_safe() };
+        alert.set_title("Share");
+        alert.set_text(format!("Copied a link to this board:\n\n{url}"));
         alert.popup_centered_minsize(Vector2::ZERO);
     }
+
+    #[method]
+    fn _on_undo_requested(&self) {
+        self.with_puzzle(Puzzle::undo);
+    }
+
+    #[method]
+    fn _on_redo_requested(&self) {
+        self.with_puzzle(Puzzle::redo);
+    }
+
+    #[method]
+    fn _on_reset_requested(&self) {
+        self.with_puzzle(Puzzle::reset);
+    }
 }
 
 impl Main {
     fn new(_base: &Node2D) -> Self {
         Main {
             puzzle_node: None,
+            editor_node: None,
             alert: None,
+            completion: None,
+            settings: Settings::default(),
+        }
+    }
+
+    fn show_endless(&mut self, base: TRef<Node2D>, seed: u64) {
+        godot_print!("endless puzzle: {}", seed);
+
+        self.show_puzzle(
+            base,
+            Puzzle::from_definition(
+                endless::puzzle(seed),
+                endless::shortname(seed),
+                self.overlay(base),
+            ),
+        );
+    }
+
+    fn show_daily(&mut self, base: TRef<Node2D>, date: daily::Date) {
+        godot_print!("daily puzzle: {}", date);
+
+        let puzzle = match daily::puzzle(date) {
+            Ok(puzzle) => puzzle,
+            Err(err) => {
+                godot_warn!("Failed to make the daily puzzle for {}: {}", date, err);
+
+                let alert = unsafe { self.alert.unwrap().assume_safe() };
+                alert.set_title("Uh oh!");
+                alert.set_text("Today's puzzle couldn't be made :(");
+                alert.popup_centered_minsize(Vector2::ZERO);
+                return;
+            }
+        };
+
+        self.show_puzzle(
+            base,
+            Puzzle::from_definition(puzzle, daily::shortname(date), self.overlay(base)),
+        );
+    }
+
+    fn show_puzzle(&mut self, base: TRef<Node2D>, puzzle: Instance<Puzzle, Unique>) {
+        self.clear(base.as_ref());
+
+        puzzle
+            .map_mut(|puzzle, _| puzzle.apply_settings(&self.settings))
+            .expect("Failed to set up puzzle");
+        // Deferred, since the puzzle is still busy checking itself when it emits this
+        puzzle
+            .base()
+            .connect(
+                "puzzle_solved",
+                base,
+                "_on_puzzle_solved",
+                VariantArray::new_shared(),
+                Object::CONNECT_DEFERRED,
+            )
+            .expect("Failed to connect to puzzle_solved signal on puzzle");
+
+        let puzzle = puzzle.into_shared();
+        self.puzzle_node = Some(puzzle.clone());
+
+        unsafe {
+            puzzle
+                .assume_safe()
+                .base()
+                .set_global_position(util::screen_center(base.as_ref().upcast::<Node>()))
+        }
+
+        base.add_child(puzzle, false);
+    }
+
+    /// Removes whatever puzzle or editor is showing
+    fn clear(&mut self, base: &Node2D) {
+        if let Some(puzzle) = self.puzzle_node.take() {
+            base.remove_child(puzzle);
+        }
+        if let Some(editor) = self.editor_node.take() {
+            base.remove_child(editor);
         }
     }
 
@@ -107,46 +444,163 @@ impl Main {
         }
     }
 
+    /// Returns where the UI layer's controls are, so pieces can be kept clear of them
+    fn overlay(&self, base: TRef<Node2D>) -> Overlay {
+        let ui = self.get_ui(base);
+        let bounds = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| get_ui_control::<Control>(ui, name).get_global_rect())
+                .reduce(Rect2::merge)
+                .expect("No controls to find the bounds of")
+        };
+
+        Overlay {
+            buttons: bounds(BUTTONS),
+            settings: bounds(SETTINGS),
+        }
+    }
+
+    fn register_check_box_callback(
+        &self,
+        base: TRef<Node2D>,
+        ui: TRef<CanvasLayer>,
+        check_box: &str,
+        pressed: bool,
+        callback: &str,
+    ) {
+        let check_box_node = get_ui_control::<CheckBox>(ui, check_box);
+        check_box_node.set_pressed_no_signal(pressed);
+        check_box_node
+            .connect("toggled", base, callback, VariantArray::new_shared(), 0)
+            .unwrap_or_else(|_| panic!("Failed to connect to toggled signal on {check_box}"));
+    }
+
     fn register_puzzle_select_callback(
         &self,
         base: TRef<Node2D>,
         ui: TRef<CanvasLayer>,
         callback: &str,
     ) {
-        unsafe {
-            ui.get_node("LevelSelectButton")
-                .expect("UI layer does not have a LevelSelectButton")
-                .assume_safe()
-                .cast::<MenuButton>()
-                .expect("LevelSelectButton is not a MenuButton")
-                .get_popup()
-                .expect("LevelSelectButton does not have a popup")
-                .assume_safe()
-        }
-        .connect(
-            "index_pressed",
+        ui.connect(
+            "puzzle_selected",
             base,
             callback,
             VariantArray::new_shared(),
             0,
         )
-        .expect("Failed to connect to index_selected signal on popup menu");
+        .expect("Failed to connect to puzzle_selected signal on UI layer");
     }
 
-    fn register_validate_callback(
+    fn register_button_callback(
         &self,
         base: TRef<Node2D>,
         ui: TRef<CanvasLayer>,
+        button: &str,
         callback: &str,
     ) {
         unsafe {
-            ui.get_node("ValidatePuzzleButton")
-                .expect("UI layer does not have a ValidatePuzzleButton")
+            ui.get_node(button)
+                .unwrap_or_else(|| panic!("UI layer does not have a {button}"))
                 .assume_safe()
                 .cast::<Button>()
-                .expect("ValidatePuzzleButton is not a Button")
+                .unwrap_or_else(|| panic!("{button} is not a Button"))
         }
         .connect("pressed", base, callback, VariantArray::new_shared(), 0)
-        .expect("Failed to connect to pressed signal on ValidatePuzzleButton");
+        .unwrap_or_else(|_| panic!("Failed to connect to pressed signal on {button}"));
+    }
+
+    /// Where the completion screen's next puzzle button goes. Catalog puzzles go on to the next
+    /// one that can be played, endless puzzles to a new one and the daily puzzle nowhere.
+    fn next_puzzle(&self) -> Option<NextPuzzle> {
+        let shortname = unsafe { self.puzzle_node.as_ref()?.assume_safe() }
+            .map(|puzzle, _| puzzle.shortname().to_string())
+            .ok()?;
+
+        if endless::seed(&shortname).is_some() {
+            return Some(NextPuzzle::Endless);
+        }
+
+        let puzzle_idx = CATALOG.index_of(&shortname)?;
+        let chapters = chapters::chapters(&CATALOG);
+        let unlocked = chapters::unlocked_count(&chapters, &CATALOG, &save::load());
+        chapters::next_puzzle(&chapters, unlocked, puzzle_idx).map(NextPuzzle::Catalog)
+    }
+
+    /// Changes the settings, saving them and applying them to the puzzle being played
+    fn update_settings(&mut self, update: impl FnOnce(&mut Settings)) {
+        update(&mut self.settings);
+
+        let settings = self.settings.clone();
+        save::update_settings(|saved| *saved = settings.clone());
+        self.with_puzzle(|puzzle| puzzle.apply_settings(&settings));
+    }
+
+    fn with_puzzle(&self, action: impl FnOnce(&mut Puzzle)) {
+        if let Some(puzzle) = self.puzzle_node.as_ref() {
+            unsafe { puzzle.assume_safe() }
+                .map_mut(|puzzle, _| action(puzzle))
+                .expect("Failed to update puzzle");
+        }
+    }
+}
+
+/// Returns the control called `name` on the UI layer
+fn get_ui_control<'a, T: GodotObject + SubClass<Node>>(
+    ui: TRef<'a, CanvasLayer>,
+    name: &str,
+) -> TRef<'a, T> {
+    unsafe {
+        ui.get_node(name)
+            .unwrap_or_else(|| panic!("UI layer does not have a {name}"))
+            .assume_safe()
+            .cast::<T>()
+            .unwrap_or_else(|| panic!("{name} is the wrong type of node"))
+    }
+}
+
+fn centered_label(text: &str) -> Ref<Label, Unique> {
+    let label = Label::new();
+    label.set_text(text);
+    label.set_align(Label::ALIGN_CENTER);
+
+    label
+}
+
+fn popup_button(target: TRef<Node2D>, text: &str, callback: &str) -> Ref<Button, Unique> {
+    let button = Button::new();
+    button.set_text(text);
+    button
+        .connect("pressed", target, callback, VariantArray::new_shared(), 0)
+        .unwrap_or_else(|_| panic!("Failed to connect to pressed signal on {text}"));
+
+    button
+}
+
+fn describe_problems(report: &ValidationReport) -> String {
+    let mut problems = vec![];
+
+    if !report.uncovered.is_empty() {
+        problems.push(format!(
+            "{} cell(s) of the board aren't covered",
+            report.uncovered.len()
+        ));
+    }
+    if !report.off_board.is_empty() {
+        problems.push(format!(
+            "{} tile(s) are hanging off the board",
+            report.off_board.len()
+        ));
     }
+    if !report.overlapping.is_empty() {
+        problems.push(format!(
+            "{} tile(s) are stacked on another piece",
+            report.overlapping.len()
+        ));
+    }
+
+    format!(
+        "There's an issue with your solution :(\n\n{}\n\nThe highlighted tiles show where.",
+        problems.join("\n")
+    )
 }
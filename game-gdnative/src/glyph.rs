@@ -0,0 +1,80 @@
+use std::f32::consts::TAU;
+
+use gdnative::prelude::*;
+
+/// How many different glyphs there are. Pieces after this many start over.
+const GLYPH_COUNT: usize = 8;
+
+/// How wide the arms of crosses and the bar are, compared to the glyph's radius
+const ARM_WIDTH: f32 = 0.35;
+
+/// Returns the outline of glyph `idx`, centred on the origin and reaching `radius` from it
+pub fn glyph(idx: usize, radius: f32) -> Vec<Vector2> {
+    match idx % GLYPH_COUNT {
+        // Circle
+        0 => star(16, radius, radius, 0.0),
+        // Triangle pointing up
+        1 => star(3, radius, radius, -TAU / 4.0),
+        // Square
+        2 => star(4, radius, radius, TAU / 8.0),
+        // Diamond
+        3 => star(4, radius, radius, 0.0),
+        // Five-pointed star
+        4 => star(5, radius, radius * 0.45, -TAU / 4.0),
+        // Plus
+        5 => cross(radius, 0.0),
+        // Cross
+        6 => cross(radius, TAU / 8.0),
+        // Bar
+        _ => {
+            let (w, h) = (radius, radius * ARM_WIDTH);
+            vec![
+                Vector2::new(-w, -h),
+                Vector2::new(w, -h),
+                Vector2::new(w, h),
+                Vector2::new(-w, h),
+            ]
+        }
+    }
+}
+
+/// Returns a polygon with `points` corners `outer` from the centre, starting at `angle`. If
+/// `inner` is shorter, there's a corner that far from the centre between each of them.
+fn star(points: usize, outer: f32, inner: f32, angle: f32) -> Vec<Vector2> {
+    let corners = if inner < outer { points * 2 } else { points };
+
+    (0..corners)
+        .map(|corner| {
+            let radius = if corner % 2 == 1 && corners > points {
+                inner
+            } else {
+                outer
+            };
+
+            Vector2::new(radius, 0.0).rotated(angle + TAU * corner as f32 / corners as f32)
+        })
+        .collect()
+}
+
+/// Returns a plus sign turned by `angle`
+fn cross(radius: f32, angle: f32) -> Vec<Vector2> {
+    let (r, w) = (radius, radius * ARM_WIDTH);
+
+    [
+        (-w, -r),
+        (w, -r),
+        (w, -w),
+        (r, -w),
+        (r, w),
+        (w, w),
+        (w, r),
+        (-w, r),
+        (-w, w),
+        (-r, w),
+        (-r, -w),
+        (-w, -w),
+    ]
+    .into_iter()
+    .map(|(x, y)| Vector2::new(x, y).rotated(angle))
+    .collect()
+}
@@ -0,0 +1,161 @@
+use gdnative::prelude::*;
+
+use crate::tile::TILE_SIDE_LEN;
+use tiler_core::board::Cell;
+use tiler_core::grid::Grid;
+use tiler_core::puzzles::Position;
+
+const SQRT_3: f32 = 1.732_050_8;
+
+/// Distance from the centre of a hex to its corners, so hexes are as wide as squares
+const HEX_RADIUS: f32 = TILE_SIDE_LEN / SQRT_3;
+const TRIANGLE_HEIGHT: f32 = TILE_SIDE_LEN * SQRT_3 / 2.0;
+
+/// The distance between the points shapes snap to. Square grids snap to half a tile, so pieces
+/// can sit between cells off the board. Hex and triangle grids snap to every other point, where
+/// a step across and a step down add up to an even number.
+pub fn step(grid: Grid) -> Vector2 {
+    match grid {
+        Grid::Square => Vector2::new(TILE_SIDE_LEN / 2.0, TILE_SIDE_LEN / 2.0),
+        Grid::Hex => Vector2::new(TILE_SIDE_LEN / 2.0, HEX_RADIUS * 1.5),
+        Grid::Triangle => Vector2::new(TILE_SIDE_LEN / 2.0, TRIANGLE_HEIGHT),
+    }
+}
+
+/// Returns the snap point nearest to `point`
+pub fn snap(grid: Grid, point: Vector2) -> Vector2 {
+    match grid {
+        Grid::Square => point.snapped(step(grid)),
+        Grid::Hex | Grid::Triangle => {
+            let steps = point / step(grid);
+            let down = steps.y.round();
+            let across = ((steps.x - down) / 2.0).round() * 2.0 + down;
+
+            Vector2::new(across, down) * step(grid)
+        }
+    }
+}
+
+/// Returns the move one snap step in `direction`, whose components are each -1, 0 or 1. Hex and
+/// triangle grids only have a snap point on every other step across, so moving sideways takes two
+/// steps and moving up or down also moves half a cell across.
+pub fn nudge(grid: Grid, direction: Vector2) -> Vector2 {
+    let steps = match grid {
+        Grid::Square => direction,
+        Grid::Hex | Grid::Triangle => Vector2::new(direction.x * 2.0 + direction.y, direction.y),
+    };
+
+    steps * step(grid)
+}
+
+/// Returns how far a cell's corner is from the corner of `Cell(0, 0)`
+pub fn cell_offset(grid: Grid, Cell(x, y): Cell) -> Vector2 {
+    let (across, down) = match grid {
+        Grid::Square => (2 * x, 2 * y),
+        Grid::Hex => (2 * x + y, y),
+        Grid::Triangle => (x, y),
+    };
+
+    Vector2::new(across as f32, down as f32) * step(grid)
+}
+
+pub fn position_offset(grid: Grid, pos: Position) -> Vector2 {
+    cell_offset(grid, Cell::from(pos))
+}
+
+/// The inverse of [`cell_offset`]. Returns `None` if `offset` isn't exactly on a cell with the
+/// same shape as `Cell(0, 0)`.
+pub fn cell_at(grid: Grid, offset: Vector2) -> Option<Cell> {
+    let steps = offset / step(grid);
+    if !steps.is_equal_approx(steps.round()) {
+        return None;
+    }
+
+    let (across, down) = (steps.x.round() as i32, steps.y.round() as i32);
+    match grid {
+        Grid::Square if across % 2 == 0 && down % 2 == 0 => Some(Cell(across / 2, down / 2)),
+        Grid::Hex if (across - down) % 2 == 0 => Some(Cell((across - down) / 2, down)),
+        Grid::Triangle if (across + down) % 2 == 0 => Some(Cell(across, down)),
+        _ => None,
+    }
+}
+
+/// The size of the box around a cell
+pub fn cell_size(grid: Grid) -> Vector2 {
+    match grid {
+        Grid::Square => Vector2::new(TILE_SIDE_LEN, TILE_SIDE_LEN),
+        Grid::Hex => Vector2::new(TILE_SIDE_LEN, HEX_RADIUS * 2.0),
+        Grid::Triangle => Vector2::new(TILE_SIDE_LEN, TRIANGLE_HEIGHT),
+    }
+}
+
+/// Returns the corners of the cell at `pos`, relative to the corner of its box, moved `inset`
+/// in from each side. A negative `inset` moves them out instead.
+pub fn cell_polygon(grid: Grid, pos: Position, inset: f32) -> Vec<Vector2> {
+    let Vector2 { x: w, y: h } = cell_size(grid);
+    let corners = match grid {
+        Grid::Square => vec![(0.0, 0.0), (w, 0.0), (w, h), (0.0, h)],
+        Grid::Hex => vec![
+            (w / 2.0, 0.0),
+            (w, h / 4.0),
+            (w, h * 0.75),
+            (w / 2.0, h),
+            (0.0, h * 0.75),
+            (0.0, h / 4.0),
+        ],
+        Grid::Triangle if (pos.0 + pos.1) % 2 == 0 => vec![(w / 2.0, 0.0), (w, h), (0.0, h)],
+        Grid::Triangle => vec![(0.0, 0.0), (w, 0.0), (w / 2.0, h)],
+    }
+    .into_iter()
+    .map(|(x, y)| Vector2::new(x, y))
+    .collect::<Vec<_>>();
+
+    // Every cell is a regular polygon, so shrinking it towards its centre moves each side in
+    // by the same amount
+    let centre = corners
+        .iter()
+        .fold(Vector2::ZERO, |sum, corner| sum + *corner)
+        / corners.len() as f32;
+    let inradius = centre.distance_to((corners[0] + corners[1]) / 2.0);
+    let scale = 1.0 - inset / inradius;
+
+    corners
+        .into_iter()
+        .map(|corner| centre + (corner - centre) * scale)
+        .collect()
+}
+
+/// Returns the centre of the cell at `pos`, relative to the corner of its box
+pub fn cell_centre(grid: Grid, pos: Position) -> Vector2 {
+    let corners = cell_polygon(grid, pos, 0.0);
+
+    corners
+        .iter()
+        .fold(Vector2::ZERO, |sum, corner| sum + *corner)
+        / corners.len() as f32
+}
+
+/// Returns the top-left and bottom-right corners of the box around the cells at `positions`
+pub fn bounds(grid: Grid, positions: &[Position]) -> (Vector2, Vector2) {
+    if positions.is_empty() {
+        return (Vector2::ZERO, Vector2::ZERO);
+    }
+
+    positions.iter().fold(
+        (
+            Vector2::new(f32::INFINITY, f32::INFINITY),
+            Vector2::new(f32::NEG_INFINITY, f32::NEG_INFINITY),
+        ),
+        |(top_left, bottom_right), pos| {
+            let corner = position_offset(grid, *pos);
+            let far_corner = corner + cell_size(grid);
+            (
+                Vector2::new(top_left.x.min(corner.x), top_left.y.min(corner.y)),
+                Vector2::new(
+                    bottom_right.x.max(far_corner.x),
+                    bottom_right.y.max(far_corner.y),
+                ),
+            )
+        },
+    )
+}
@@ -1,18 +1,27 @@
 use gdnative::prelude::*;
 
-mod puzzles;
 pub mod util;
 
+mod daily;
+mod editor;
+mod endless;
 mod game;
+mod glyph;
+mod grid;
+mod packs;
+mod palette;
 mod puzzle;
+mod save;
 mod shape;
 mod tile;
+mod tray;
 mod ui;
 
 fn init(handle: InitHandle) {
     handle.add_class::<tile::Tile>();
     handle.add_class::<shape::Shape>();
     handle.add_class::<puzzle::Puzzle>();
+    handle.add_class::<editor::Editor>();
     handle.add_class::<ui::UI>();
     handle.add_class::<game::Main>();
 }
@@ -0,0 +1,154 @@
+use gdnative::api::{Directory, JavaScript};
+use gdnative::prelude::*;
+use lazy_static::lazy_static;
+
+use crate::util;
+use tiler_core::catalog::{Catalog, Loaded, PACK_SUFFIX};
+use tiler_core::difficulty::Difficulty;
+use tiler_core::puzzles::PuzzleDefinition;
+include!(concat!(env!("OUT_DIR"), "/puzzle_definitions.rs"));
+
+/// Directories searched for puzzle packs at startup. Everything under them is loaded, so a pack
+/// can be a directory of puzzle files laid out like `src/puzzles`, or a single pack file.
+const PACK_DIRS: [&str; 2] = ["res://packs", "user://packs"];
+
+lazy_static! {
+    /// The puzzles built into the game, followed by the ones from every pack
+    pub static ref CATALOG: Catalog = load_catalog();
+}
+
+fn load_catalog() -> Catalog {
+    let mut catalog = Catalog::default();
+
+    for ((shortname, puzzle), difficulty) in PUZZLE_SHORT_NAMES
+        .iter()
+        .zip(PUZZLES.iter())
+        .zip(PUZZLE_DIFFICULTIES.iter())
+    {
+        catalog.insert_rated(
+            shortname.to_string(),
+            serde_yaml::from_str::<PuzzleDefinition>(puzzle).unwrap(),
+            Some(Difficulty(*difficulty)),
+        );
+    }
+
+    for dir in PACK_DIRS {
+        // Puzzle files are sorted like the built-in ones, while pack files keep their own order
+        let (pack_files, puzzle_files): (Vec<String>, Vec<String>) = list_files(dir)
+            .into_iter()
+            .partition(|path| path.ends_with(PACK_SUFFIX));
+
+        let start = catalog.entries().len();
+        for path in puzzle_files {
+            load_file(&mut catalog, dir, &path);
+        }
+        catalog.sort_from(start);
+
+        for path in pack_files {
+            load_file(&mut catalog, dir, &path);
+        }
+    }
+
+    // Web builds can also load a pack file from `?pack=<url>`
+    if let Some(url) = util::url_param("pack") {
+        match fetch(&url) {
+            Some(contents) => report(&url, Some(catalog.insert_pack(&contents))),
+            None => godot_warn!("Failed to download puzzle pack {}", url),
+        }
+    }
+
+    // Only puzzles from packs are left to rate
+    catalog.rate_all();
+
+    catalog
+}
+
+fn load_file(catalog: &mut Catalog, dir: &str, path: &str) {
+    if let Some(contents) = util::read_file(&format!("{dir}/{path}")) {
+        report(path, catalog.insert_file(path, contents));
+    }
+}
+
+fn report(path: &str, loaded: Option<Result<Loaded, serde_yaml::Error>>) {
+    match loaded {
+        Some(Ok(loaded)) => {
+            for (shortname, err) in loaded.skipped {
+                let location = match err.line() {
+                    Some(line) => format!("{path}:{line}"),
+                    None => path.to_string(),
+                };
+                godot_warn!("Skipping {} from {}: {}", shortname, location, err);
+            }
+            godot_print!("Loaded {} puzzle(s) from {}", loaded.added, path);
+        }
+        Some(Err(err)) => godot_warn!("Skipping {}: {}", path, err),
+        None => {}
+    }
+}
+
+/// Returns the path of every file under `dir`, relative to it. The paths are sorted, since
+/// directory listings come back in whatever order the platform likes.
+fn list_files(dir: &str) -> Vec<String> {
+    let mut files = vec![];
+    let mut subdirs = vec![String::new()];
+
+    while let Some(subdir) = subdirs.pop() {
+        let directory = Directory::new();
+        let path = if subdir.is_empty() {
+            dir.to_string()
+        } else {
+            format!("{dir}/{subdir}")
+        };
+
+        if directory.open(path).is_err() || directory.list_dir_begin(true, true).is_err() {
+            continue;
+        }
+
+        loop {
+            let name = directory.get_next().to_string();
+            if name.is_empty() {
+                break;
+            }
+
+            let relative = if subdir.is_empty() {
+                name
+            } else {
+                format!("{subdir}/{name}")
+            };
+
+            if directory.current_is_dir() {
+                subdirs.push(relative);
+            } else {
+                files.push(relative);
+            }
+        }
+
+        directory.list_dir_end();
+    }
+
+    files.sort();
+    files
+}
+
+/// Downloads a text file. This blocks until it's done, which is fine while the game is
+/// starting up.
+fn fetch(url: &str) -> Option<String> {
+    JavaScript::godot_singleton()
+        .eval(
+            format!(
+                "(function() {{
+                    try {{
+                        const request = new XMLHttpRequest();
+                        request.open('GET', {}, false);
+                        request.send();
+                        return request.status === 200 ? request.responseText : null;
+                    }} catch (err) {{
+                        return null;
+                    }}
+                }})()",
+                util::js_string(url)
+            ),
+            true,
+        )
+        .to::<String>()
+}
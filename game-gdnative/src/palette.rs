@@ -0,0 +1,110 @@
+use gdnative::prelude::*;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use tiler_core::palette::{Palette, HIGH_CONTRAST};
+use tiler_core::puzzles::PuzzleDefinition;
+
+/// Used for colors which don't parse. Puzzles in the catalog are linted, so this only shows up
+/// in puzzles loaded some other way.
+const FALLBACK_COLOR: Color = Color {
+    r: 0.5f32,
+    g: 0.5f32,
+    b: 0.5f32,
+    a: 1.0f32,
+};
+
+/// Used for shapes fixed in place when the colors come from a list. Lists are only spent on the
+/// pieces, so the first color goes to the first piece rather than the board.
+const FIXED_COLOR: Color = Color {
+    r: 0.4f32,
+    g: 0.4f32,
+    b: 0.4f32,
+    a: 1.0f32,
+};
+
+/// Returns a color for each shape of `puzzle`. Gradients are shuffled the same way for every
+/// puzzle with the same number of shapes, so neighbouring pieces don't get similar shades.
+pub fn shape_colors(puzzle: &PuzzleDefinition, palette: Palette) -> Vec<Color> {
+    let mut colors = palette_colors(puzzle, palette);
+
+    // Shapes' own colors replace the warm palette, the same as the puzzle's palette does
+    if palette == Palette::Warm {
+        for (color, shape) in colors.iter_mut().zip(puzzle.shapes.iter()) {
+            if let Some(shape_color) = shape.color.as_deref() {
+                *color = parse(shape_color);
+            }
+        }
+    }
+
+    colors
+}
+
+fn palette_colors(puzzle: &PuzzleDefinition, palette: Palette) -> Vec<Color> {
+    let count = puzzle.shapes.len();
+
+    let gradient = match palette {
+        Palette::Warm if !puzzle.palette.is_empty() => return cycle(&puzzle.palette, puzzle),
+        Palette::HighContrast => return cycle(&HIGH_CONTRAST, puzzle),
+        Palette::Warm => colorgrad::warm(),
+        Palette::Viridis => colorgrad::viridis(),
+        Palette::Cividis => colorgrad::cividis(),
+    };
+
+    let mut colors = gradient.colors(count);
+    colors.shuffle(&mut ChaCha8Rng::seed_from_u64(count as u64));
+
+    colors.iter().map(to_godot).collect()
+}
+
+/// Returns a color to draw on top of `background` which stands out from it
+pub fn contrasting(background: Color) -> Color {
+    let luminance = 0.2126 * background.r + 0.7152 * background.g + 0.0722 * background.b;
+
+    if luminance > 0.5 {
+        Color::from_rgba(0.0, 0.0, 0.0, 0.7)
+    } else {
+        Color::from_rgba(1.0, 1.0, 1.0, 0.8)
+    }
+}
+
+/// Returns a color for each shape of `puzzle`, giving its pieces `colors` in order and starting
+/// over once they run out
+fn cycle(colors: &[impl AsRef<str>], puzzle: &PuzzleDefinition) -> Vec<Color> {
+    let mut colors = colors.iter().cycle();
+
+    puzzle
+        .shapes
+        .iter()
+        .map(|shape| {
+            if shape.interactable {
+                colors
+                    .next()
+                    .map_or(FALLBACK_COLOR, |color| parse(color.as_ref()))
+            } else {
+                FIXED_COLOR
+            }
+        })
+        .collect()
+}
+
+/// Parses a hex code or CSS color name
+fn parse(color: &str) -> Color {
+    match colorgrad::Color::from_html(color) {
+        Ok(color) => to_godot(&color),
+        Err(err) => {
+            godot_warn!("Failed to parse color {}: {}", color, err);
+            FALLBACK_COLOR
+        }
+    }
+}
+
+fn to_godot(color: &colorgrad::Color) -> Color {
+    Color {
+        r: color.r as f32,
+        g: color.g as f32,
+        b: color.b as f32,
+        a: color.a as f32,
+    }
+}
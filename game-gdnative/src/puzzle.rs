@@ -1,81 +1,251 @@
+use gdnative::api::{GlobalConstants, InputEventMouseButton, InputEventScreenDrag};
 use gdnative::prelude::*;
-use rand::seq::SliceRandom;
-use rand::SeedableRng;
-use rand_chacha::ChaCha8Rng;
 
-use crate::shape::{Shape, GLOBAL_GRID_SNAP};
+use crate::grid;
+use crate::packs::CATALOG;
+use crate::palette;
+use crate::save;
+use crate::shape::{Shape, ShapeMove, ShapeState};
+use crate::tile::TILE_SIDE_LEN;
+use crate::tray::{self, Overlay};
+use tiler_core::board::{Board, Placement, TileRef, ValidationReport};
+use tiler_core::hints::{self, Hint};
+use tiler_core::history::History;
+use tiler_core::palette::Palette;
+use tiler_core::puzzles::{Position, PuzzleDefinition};
+use tiler_core::save::{PuzzleProgress, SavedShape, Settings};
+use tiler_core::share;
 
-use crate::{puzzles::PuzzleDefinition, tile::TILE_SIDE_LEN};
-include!(concat!(env!("OUT_DIR"), "/puzzle_definitions.rs"));
+const UNCOVERED_HIGHLIGHT: Color = Color {
+    r: 1.0f32,
+    g: 0.8f32,
+    b: 0.2f32,
+    a: 1.0f32,
+};
+
+const OFF_BOARD_HIGHLIGHT: Color = Color {
+    r: 1.0f32,
+    g: 0.1f32,
+    b: 0.1f32,
+    a: 1.0f32,
+};
+
+const OVERLAPPING_HIGHLIGHT: Color = Color {
+    r: 0.9f32,
+    g: 0.2f32,
+    b: 0.9f32,
+    a: 1.0f32,
+};
+
+const HINT_HIGHLIGHT: Color = Color {
+    r: 0.2f32,
+    g: 0.8f32,
+    b: 1.0f32,
+    a: 1.0f32,
+};
+
+/// How far a turn of the mouse wheel scrolls the view
+const SCROLL_STEP: f32 = TILE_SIDE_LEN;
+
+/// Actions which move the selected piece, and the direction they move it in
+const MOVE_ACTIONS: [(&str, Vector2); 4] = [
+    ("move_piece_left", Vector2 { x: -1.0, y: 0.0 }),
+    ("move_piece_right", Vector2 { x: 1.0, y: 0.0 }),
+    ("move_piece_up", Vector2 { x: 0.0, y: -1.0 }),
+    ("move_piece_down", Vector2 { x: 0.0, y: 1.0 }),
+];
 
 #[derive(NativeClass)]
 #[inherit(Node2D)]
 #[no_constructor]
+#[register_with(Self::register)]
 pub struct Puzzle {
+    board: Board,
     shapes: Vec<Instance<Shape>>,
+    definition: PuzzleDefinition,
+    /// The name progress is saved under and shared links point at
+    shortname: String,
+
+    history: History<Vec<ShapeMove>>,
+    initial_states: Vec<ShapeState>,
+
+    /// Seconds spent on the current attempt
+    elapsed: f64,
+    /// Moves made in the current attempt, counting undos and redos
+    moves: usize,
+    /// Hints taken during the current attempt
+    hints: usize,
+    /// The piece the last hint showed the spot for. Asking again puts it there.
+    hinted_piece: Option<usize>,
+    /// Whether the current attempt has been validated as a solution, which stops the clock
+    finished: bool,
+    /// The piece picked up with the keyboard or a gamepad
+    selected: Option<usize>,
+    /// Fingers dragging or twisting a piece. Tiles reach past their edges on small screens, so
+    /// a finger can land on more than one piece, but only the first one it reaches takes it.
+    fingers: Vec<i64>,
+    /// Check the board whenever a piece is dropped
+    auto_validate: bool,
+    /// The palette the pieces are colored with
+    palette: Palette,
+    /// How far the view is scrolled down, to reach pieces which didn't fit in the window
+    scroll: f32,
+    /// The controls drawn over the puzzle, which pieces are scrolled clear of
+    overlay: Overlay,
+    /// Set when a piece is dropped with `auto_validate` on. Like saving, checking the board
+    /// waits for the next frame.
+    unchecked: bool,
+    /// Set when the board changes. Saving reads every shape, which can't happen while a shape
+    /// is still handling the input that moved it, so the save waits for the next frame.
+    unsaved: bool,
 }
 
 #[methods]
-impl Puzzle {}
+impl Puzzle {
+    #[method]
+    fn _ready(&mut self, #[base] base: &Node2D) {
+        // Children are ready first, so every shape is already in its starting spot
+        self.initial_states = self.shape_states();
+
+        if let Some(progress) = save::load().progress(&self.shortname) {
+            self.restore(base, progress);
+        }
+    }
+
+    #[method]
+    fn _process(&mut self, #[base] base: TRef<Node2D>, delta: f64) {
+        if !self.finished {
+            self.elapsed += delta;
+        }
+
+        // Only a solved board is worth saying anything about, so problems aren't highlighted
+        // while the player is still part way through
+        if self.unchecked {
+            self.unchecked = false;
+            if !self.finished && self.board.validate(&self.placements()).is_valid() {
+                self.validate(base);
+            }
+        }
+
+        if self.unsaved {
+            self.unsaved = false;
+            self.save_progress(base.as_ref(), false);
+        }
+    }
+
+    #[method]
+    fn _exit_tree(&mut self, #[base] base: &Node2D) {
+        // The view is shared with whatever's shown next
+        self.scroll_to(base, 0f32);
+    }
+
+    #[method]
+    fn _unhandled_input(&mut self, #[base] base: &Node2D, event: Ref<InputEvent>) {
+        if let Some(button) = event.clone().cast::<InputEventMouseButton>() {
+            let button = unsafe { button.assume_safe() };
+            let direction = match button.button_index() {
+                GlobalConstants::BUTTON_WHEEL_UP => -1f32,
+                GlobalConstants::BUTTON_WHEEL_DOWN => 1f32,
+                _ => 0f32,
+            };
+            // Scrolling away from a piece being dragged with the mouse would leave it behind, and
+            // the wheel turns pieces under the pointer instead
+            let dragging =
+                Input::godot_singleton().is_mouse_button_pressed(GlobalConstants::BUTTON_LEFT);
+            if direction != 0f32
+                && !dragging
+                && !self.can_turn_piece_at(base.get_global_mouse_position())
+            {
+                if button.is_pressed() {
+                    self.scroll_to(base, self.scroll + direction * SCROLL_STEP);
+                }
+                unsafe { base.get_tree().unwrap().assume_safe() }.set_input_as_handled();
+                return;
+            }
+        }
+
+        // Fingers dragging pieces are handled by the pieces, so any others pan the view
+        if let Some(drag) = event.clone().cast::<InputEventScreenDrag>() {
+            let drag = unsafe { drag.assume_safe() };
+            if !self.fingers.contains(&drag.index()) {
+                self.scroll_to(base, self.scroll - drag.relative().y);
+            }
+            return;
+        }
+
+        let event = unsafe { event.assume_safe() };
+
+        // Match exactly, otherwise Ctrl+Shift+Z would also count as Ctrl+Z
+        if event.is_action_pressed("redo", false, true) {
+            self.redo();
+        } else if event.is_action_pressed("undo", false, true) {
+            self.undo();
+        } else if event.is_action_pressed("select_previous_piece", false, true) {
+            self.cycle_selection(false);
+            self.show_selected(base);
+        } else if event.is_action_pressed("select_next_piece", false, true) {
+            self.cycle_selection(true);
+            self.show_selected(base);
+        } else if event.is_action_pressed("drop_piece", false, false) {
+            self.drop_selected();
+        } else if let Some((_, direction)) = MOVE_ACTIONS
+            .iter()
+            .find(|(action, _)| event.is_action_pressed(*action, true, false))
+        {
+            self.nudge_selected(*direction);
+            self.show_selected(base);
+        }
+    }
+}
 
 impl Puzzle {
-    pub fn from_idx(idx: usize) -> Instance<Self, Unique> {
-        let puzzle = serde_yaml::from_str::<PuzzleDefinition>(&PUZZLES[idx]).unwrap();
+    pub fn from_idx(idx: usize, overlay: Overlay) -> Instance<Self, Unique> {
+        let entry = CATALOG.get(idx).expect("No puzzle with that index");
+        Self::from_definition(entry.definition.clone(), entry.shortname.clone(), overlay)
+    }
 
-        let mut shape_colors = colorgrad::warm().colors(puzzle.shapes.len());
-        shape_colors.shuffle(&mut ChaCha8Rng::seed_from_u64(puzzle.shapes.len() as u64));
+    pub fn from_definition(
+        puzzle: PuzzleDefinition,
+        shortname: String,
+        overlay: Overlay,
+    ) -> Instance<Self, Unique> {
+        let shape_colors = palette::shape_colors(&puzzle, Palette::default());
+        let positions = tray::start_positions(
+            &puzzle,
+            gdnative::api::OS::godot_singleton().window_size(),
+            overlay,
+        );
 
         let instance = Self {
+            board: Board::from_definition(&puzzle),
             shapes: puzzle
                 .shapes
                 .iter()
-                .zip(shape_colors.iter())
-                .scan(
-                    (GLOBAL_GRID_SNAP, 0f32),
-                    |(tl_pos, max_col_width), (shape_def, raw_color)| {
-                        let shape_color = Color {
-                            r: raw_color.r as f32,
-                            b: raw_color.b as f32,
-                            g: raw_color.g as f32,
-                            a: raw_color.a as f32,
-                        };
-
-                        let shape = if shape_def.interactable {
-                            let (shape, shape_size) =
-                                Shape::from_definition(*tl_pos, shape_def, shape_color);
-
-                            if shape_def.pos.is_none() {
-                                *tl_pos += Vector2::new(
-                                    0f32,
-                                    shape_size.y * TILE_SIDE_LEN + GLOBAL_GRID_SNAP.y,
-                                );
-
-                                *max_col_width =
-                                    f32::max(*max_col_width, shape_size.x * TILE_SIDE_LEN);
-                            }
-
-                            shape
-                        } else {
-                            Shape::from_definition(GLOBAL_GRID_SNAP, shape_def, shape_color).0
-                        };
-
-                        // If we're outside of the window, reset to the next column
-                        if tl_pos.y
-                            >= gdnative::api::OS::godot_singleton().window_size().y
-                                - GLOBAL_GRID_SNAP.y * 2.0
-                        {
-                            *tl_pos = Vector2::new(
-                                tl_pos.x + *max_col_width + GLOBAL_GRID_SNAP.x,
-                                GLOBAL_GRID_SNAP.y,
-                            );
-                            *max_col_width = 0f32;
-                        }
-
-                        Some(shape)
-                    },
-                )
+                .zip(shape_colors)
+                .zip(positions)
+                .enumerate()
+                .map(|(shape_idx, ((shape_def, shape_color), position))| {
+                    Shape::from_definition(position, shape_idx, shape_def, &puzzle, shape_color)
+                })
                 .map(Instance::into_shared)
                 .collect(),
+            definition: puzzle,
+            shortname,
+            history: History::default(),
+            initial_states: vec![],
+            elapsed: 0f64,
+            moves: 0,
+            hints: 0,
+            hinted_piece: None,
+            finished: false,
+            selected: None,
+            fingers: vec![],
+            auto_validate: false,
+            palette: Palette::default(),
+            scroll: 0f32,
+            overlay,
+            unchecked: false,
+            unsaved: false,
         }
         .emplace();
 
@@ -92,11 +262,496 @@ impl Puzzle {
         instance
     }
 
-    pub fn validate(&self, _base: TRef<Node2D>) -> bool {
-        self.shapes.iter().all(|shape| {
+    fn register(builder: &ClassBuilder<Self>) {
+        builder
+            .signal("puzzle_solved")
+            .with_param("elapsed", VariantType::F64)
+            .with_param("moves", VariantType::I64)
+            .done();
+    }
+
+    pub fn shortname(&self) -> &str {
+        &self.shortname
+    }
+
+    /// Hints taken during the current attempt
+    pub fn hints(&self) -> usize {
+        self.hints
+    }
+
+    /// Applies the player's settings, recoloring the pieces if the palette has changed
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.auto_validate = settings.auto_validate;
+
+        if settings.palette != self.palette {
+            self.palette = settings.palette;
+
+            let colors = palette::shape_colors(&self.definition, self.palette);
+            for (shape, color) in self.shapes.iter().zip(colors) {
+                unsafe { shape.assume_safe() }
+                    .map(|shape, _| shape.set_color(color))
+                    .expect("Failed to color shape");
+            }
+        }
+
+        for shape in &self.shapes {
+            unsafe { shape.assume_safe() }
+                .map(|shape, _| shape.set_glyphs(settings.glyphs))
+                .expect("Failed to show shape glyphs");
+        }
+    }
+
+    /// Called when the player lets go of a piece
+    pub fn shape_dropped(&mut self) {
+        if self.auto_validate {
+            self.unchecked = true;
+        }
+    }
+
+    /// Puts down the selected piece and picks up the next interactable one, or the previous one
+    /// if `forwards` isn't set
+    fn cycle_selection(&mut self, forwards: bool) {
+        let interactable = self
+            .definition
+            .shapes
+            .iter()
+            .enumerate()
+            .filter(|(_, shape_def)| shape_def.interactable)
+            .map(|(shape_idx, _)| shape_idx)
+            .collect::<Vec<_>>();
+        if interactable.is_empty() {
+            return;
+        }
+
+        let next = match self
+            .selected
+            .and_then(|selected| interactable.iter().position(|idx| *idx == selected))
+        {
+            Some(current) if forwards => (current + 1) % interactable.len(),
+            Some(current) => (current + interactable.len() - 1) % interactable.len(),
+            None if forwards => 0,
+            None => interactable.len() - 1,
+        };
+
+        self.drop_selected();
+
+        let shape_idx = interactable[next];
+        unsafe { self.shapes[shape_idx].assume_safe() }
+            .map_mut(|shape, shape_node| shape.select(shape_node.as_ref()))
+            .expect("Failed to select shape");
+        self.selected = Some(shape_idx);
+    }
+
+    /// Puts down the selected piece, recording everything it did while it was picked up as a
+    /// single move
+    fn drop_selected(&mut self) {
+        let shape_idx = match self.selected.take() {
+            Some(shape_idx) => shape_idx,
+            None => return,
+        };
+
+        let shape_move = unsafe { self.shapes[shape_idx].assume_safe() }
+            .map_mut(|shape, shape_node| shape.deselect(shape_node.as_ref()))
+            .expect("Failed to deselect shape");
+        if let Some(shape_move) = shape_move {
+            self.record(vec![shape_move]);
+        }
+
+        self.shape_dropped();
+    }
+
+    fn nudge_selected(&mut self, direction: Vector2) {
+        if let Some(shape_idx) = self.selected {
+            unsafe { self.shapes[shape_idx].assume_safe() }
+                .map_mut(|shape, shape_node| shape.nudge(shape_node.as_ref(), direction))
+                .expect("Failed to move shape");
+        }
+    }
+
+    /// Scrolls the view down to `scroll`, as far as there are pieces to see
+    fn scroll_to(&mut self, base: &Node2D, scroll: f32) {
+        let bottom = self
+            .shapes
+            .iter()
+            .map(|shape| shape_bounds(shape).end().y)
+            .fold(0f32, f32::max);
+        let limit = tray::scroll_limit(bottom, self.overlay);
+        self.scroll = scroll.min(limit).max(0f32);
+
+        unsafe { base.get_viewport().unwrap().assume_safe() }.set_canvas_transform(Transform2D {
+            origin: Vector2::new(0f32, -self.scroll),
+            ..Transform2D::IDENTITY
+        });
+    }
+
+    /// Returns true if a piece which can turn has a tile at `point`
+    fn can_turn_piece_at(&self, point: Vector2) -> bool {
+        self.shapes.iter().any(|shape| {
             unsafe { shape.assume_safe() }
-                .map(|shape, shape_node| shape.validate(shape_node, &self.shapes))
-                .unwrap_or(false)
+                .map(|shape, _| shape.can_turn_at(point))
+                .expect("Failed to check shape tiles")
         })
     }
+
+    /// Scrolls the selected piece into view
+    fn show_selected(&mut self, base: &Node2D) {
+        if let Some(shape_idx) = self.selected {
+            let bounds = shape_bounds(&self.shapes[shape_idx]);
+            let scroll = tray::scroll_to_show(self.scroll, bounds, self.overlay);
+            self.scroll_to(base, scroll);
+        }
+    }
+
+    /// Gives `finger` to the piece asking for it. Returns false if another piece already has it.
+    pub fn claim_finger(&mut self, finger: i64) -> bool {
+        if self.fingers.contains(&finger) {
+            return false;
+        }
+
+        self.fingers.push(finger);
+        true
+    }
+
+    pub fn release_finger(&mut self, finger: i64) {
+        self.fingers.retain(|claimed| *claimed != finger);
+    }
+
+    pub fn record(&mut self, moves: Vec<ShapeMove>) {
+        self.history.record(moves);
+        self.moves += 1;
+        self.unsaved = true;
+    }
+
+    pub fn undo(&mut self) {
+        self.drop_selected();
+        if let Some(moves) = self.history.undo() {
+            for shape_move in moves.iter().rev() {
+                set_shape_state(&self.shapes[shape_move.shape_idx], shape_move.from);
+            }
+
+            self.moves += 1;
+            self.unsaved = true;
+        }
+    }
+
+    pub fn redo(&mut self) {
+        self.drop_selected();
+        if let Some(moves) = self.history.redo() {
+            for shape_move in moves {
+                set_shape_state(&self.shapes[shape_move.shape_idx], shape_move.to);
+            }
+
+            self.moves += 1;
+            self.unsaved = true;
+        }
+    }
+
+    /// Moves every shape back to where it started, as a single undoable move. This also
+    /// starts a fresh attempt, so the clock and move count start over. Hints already taken
+    /// still count, since the player has seen them.
+    pub fn reset(&mut self) {
+        self.drop_selected();
+        self.elapsed = 0f64;
+        self.moves = 0;
+        self.finished = false;
+
+        let moves = self
+            .shape_states()
+            .into_iter()
+            .zip(self.initial_states.iter())
+            .enumerate()
+            .filter(|(_, (from, to))| from != *to)
+            .map(|(shape_idx, (from, to))| ShapeMove {
+                shape_idx,
+                from,
+                to: *to,
+            })
+            .collect::<Vec<ShapeMove>>();
+
+        if !moves.is_empty() {
+            for shape_move in &moves {
+                set_shape_state(&self.shapes[shape_move.shape_idx], shape_move.to);
+            }
+            self.history.record(moves);
+        }
+
+        self.unsaved = true;
+    }
+
+    /// Puts the shapes back where they were left and carries on the saved attempt. An attempt
+    /// which was left solved stays finished, so the clock stays stopped.
+    fn restore(&mut self, base: &Node2D, progress: &PuzzleProgress) {
+        self.elapsed = progress.elapsed;
+        self.moves = progress.moves;
+        self.hints = progress.hints;
+
+        // The puzzle has changed since the save, so the old layout doesn't fit it
+        if progress.shapes.len() == self.shapes.len() {
+            self.set_layout(base, &progress.shapes);
+            self.finished = self.board.validate(&self.placements()).is_valid();
+        }
+    }
+
+    /// Saves the current attempt, marking the puzzle as solved if `solved` is set
+    fn save_progress(&self, base: &Node2D, solved: bool) {
+        let shapes = self.layout(base);
+
+        save::update_progress(&self.shortname, |progress| {
+            progress.elapsed = self.elapsed;
+            progress.moves = self.moves;
+            progress.hints = self.hints;
+            progress.shapes = shapes;
+
+            if solved {
+                progress.record_solve();
+            }
+        });
+    }
+
+    /// Returns where every shape is, relative to the puzzle
+    fn layout(&self, base: &Node2D) -> Vec<SavedShape> {
+        let origin = base.global_position();
+
+        self.shape_states()
+            .into_iter()
+            .map(|state| {
+                let steps = ((state.position - origin) / grid::step(self.definition.grid)).round();
+
+                SavedShape {
+                    x: steps.x as i32,
+                    y: steps.y as i32,
+                    orientation: state.orientation,
+                }
+            })
+            .collect()
+    }
+
+    fn set_layout(&self, base: &Node2D, layout: &[SavedShape]) {
+        let origin = base.global_position();
+
+        for (shape, saved) in self.shapes.iter().zip(layout.iter()) {
+            set_shape_state(
+                shape,
+                ShapeState {
+                    position: origin
+                        + Vector2::new(saved.x as f32, saved.y as f32)
+                            * grid::step(self.definition.grid),
+                    orientation: saved.orientation,
+                },
+            );
+        }
+    }
+
+    /// Returns the URL query which opens this puzzle with the board as it is now
+    pub fn share_query(&self, base: TRef<Node2D>) -> String {
+        format!(
+            "?puzzle={}&state={}",
+            self.shortname,
+            share::encode_state(&self.layout(base.as_ref()))
+        )
+    }
+
+    /// Lays the board out as it was shared, as a single undoable move. States which don't fit
+    /// this puzzle are ignored.
+    pub fn load_shared_state(&mut self, base: TRef<Node2D>, state: &str) {
+        let layout = match share::decode_state(state) {
+            Ok(layout) if layout.len() == self.shapes.len() => layout,
+            Ok(_) => {
+                godot_warn!("Shared board state doesn't match {}", self.shortname);
+                return;
+            }
+            Err(err) => {
+                godot_warn!("Failed to load shared board state: {}", err);
+                return;
+            }
+        };
+
+        let from = self.shape_states();
+        self.set_layout(base.as_ref(), &layout);
+
+        let moves = from
+            .into_iter()
+            .zip(self.shape_states())
+            .enumerate()
+            .filter(|(_, (from, to))| from != to)
+            .map(|(shape_idx, (from, to))| ShapeMove {
+                shape_idx,
+                from,
+                to,
+            })
+            .collect::<Vec<ShapeMove>>();
+
+        if !moves.is_empty() {
+            self.history.record(moves);
+            self.unsaved = true;
+        }
+    }
+
+    fn shape_states(&self) -> Vec<ShapeState> {
+        self.shapes
+            .iter()
+            .map(|shape| {
+                unsafe { shape.assume_safe() }
+                    .map(|shape, shape_node| shape.state(shape_node.as_ref()))
+                    .expect("Failed to get shape state")
+            })
+            .collect()
+    }
+
+    /// Validates the current placements and highlights every problem tile. The first valid
+    /// solution of an attempt is saved as a solve and emits `puzzle_solved`, so checking a
+    /// board that's already solved doesn't show the completion screen again.
+    pub fn validate(&mut self, base: TRef<Node2D>) -> ValidationReport {
+        let report = self.board.validate(&self.placements());
+        self.highlight(&report);
+
+        if report.is_valid() && !self.finished {
+            self.finished = true;
+            self.unsaved = false;
+            self.save_progress(base.as_ref(), true);
+
+            base.emit_signal(
+                "puzzle_solved",
+                &[self.elapsed.to_variant(), (self.moves as i64).to_variant()],
+            );
+        }
+
+        report
+    }
+
+    /// Asks the solver what to do next and shows the answer. The first hint for a piece shows
+    /// where it goes, and asking again puts it there.
+    pub fn hint(&mut self) -> Hint {
+        self.drop_selected();
+        let hint = hints::hint(&self.board, &self.placements());
+        self.clear_highlights();
+
+        match &hint {
+            Hint::Place {
+                placement,
+                board_tiles,
+            } => {
+                if self.hinted_piece == Some(placement.shape_idx) {
+                    self.hinted_piece = None;
+                    self.snap(placement);
+                    self.shape_dropped();
+                } else {
+                    self.hinted_piece = Some(placement.shape_idx);
+                    self.hints += 1;
+                    for tile in board_tiles {
+                        self.highlight_tile(*tile, HINT_HIGHLIGHT);
+                    }
+                }
+            }
+            Hint::Misplaced(shape_idx) => {
+                self.hinted_piece = None;
+                self.hints += 1;
+                unsafe { self.shapes[*shape_idx].assume_safe() }
+                    .map(|shape, _| shape.highlight(OFF_BOARD_HIGHLIGHT))
+                    .expect("Failed to highlight shape");
+            }
+            Hint::Solved | Hint::Unsolvable => self.hinted_piece = None,
+        }
+
+        self.unsaved = true;
+        hint
+    }
+
+    /// Moves a piece straight to `placement`, as a single undoable move
+    fn snap(&mut self, placement: &Placement) {
+        let shape = &self.shapes[placement.shape_idx];
+        let from = unsafe { shape.assume_safe() }
+            .map(|shape, shape_node| shape.state(shape_node.as_ref()))
+            .expect("Failed to get shape state");
+        let to = ShapeState {
+            position: self.board_origin()
+                + grid::cell_offset(self.definition.grid, placement.offset),
+            orientation: placement.orientation,
+        };
+
+        set_shape_state(shape, to);
+        self.record(vec![ShapeMove {
+            shape_idx: placement.shape_idx,
+            from,
+            to,
+        }]);
+    }
+
+    fn clear_highlights(&self) {
+        self.shapes.iter().for_each(|shape| {
+            unsafe { shape.assume_safe() }
+                .map(|shape, _| shape.clear_highlights())
+                .expect("Failed to clear shape highlights")
+        });
+    }
+
+    fn highlight_tile(&self, tile: TileRef, highlight: Color) {
+        unsafe { self.shapes[tile.shape_idx].assume_safe() }
+            .map(|shape, _| shape.highlight_tile(tile.tile_idx, highlight))
+            .expect("Failed to highlight shape");
+    }
+
+    fn highlight(&self, report: &ValidationReport) {
+        self.clear_highlights();
+
+        for (tiles, highlight) in [
+            (&report.uncovered, UNCOVERED_HIGHLIGHT),
+            (&report.off_board, OFF_BOARD_HIGHLIGHT),
+            (&report.overlapping, OVERLAPPING_HIGHLIGHT),
+        ] {
+            for tile in tiles {
+                self.highlight_tile(*tile, highlight);
+            }
+        }
+    }
+
+    /// Returns the global position of the board's `Cell(0, 0)`, anchored on the first
+    /// non-interactable shape
+    fn board_origin(&self) -> Vector2 {
+        self.definition
+            .shapes
+            .iter()
+            .zip(self.shapes.iter())
+            .find(|(shape_def, _)| !shape_def.interactable)
+            .map(|(shape_def, shape)| {
+                let shape_pos = unsafe { shape.assume_safe() }.base().global_position();
+                let board_pos = shape_def.pos.unwrap_or(Position(0, 0));
+
+                shape_pos - grid::position_offset(self.definition.grid, board_pos)
+            })
+            .unwrap_or(Vector2::ZERO)
+    }
+
+    /// Returns the placement of every interactable shape that's aligned with the board grid
+    fn placements(&self) -> Vec<Placement> {
+        let origin = self.board_origin();
+
+        self.definition
+            .shapes
+            .iter()
+            .zip(self.shapes.iter())
+            .enumerate()
+            .filter(|(_, (shape_def, _))| shape_def.interactable)
+            .filter_map(|(shape_idx, (_, shape))| {
+                unsafe { shape.assume_safe() }
+                    .map(|shape, shape_node| {
+                        shape.placement(shape_node.as_ref(), origin, shape_idx)
+                    })
+                    .ok()
+                    .flatten()
+            })
+            .collect()
+    }
+}
+
+fn set_shape_state(shape: &Instance<Shape>, state: ShapeState) {
+    unsafe { shape.assume_safe() }
+        .map_mut(|shape, shape_node| shape.set_state(shape_node.as_ref(), state))
+        .expect("Failed to set shape state");
+}
+
+fn shape_bounds(shape: &Instance<Shape>) -> Rect2 {
+    unsafe { shape.assume_safe() }
+        .map(|shape, shape_node| shape.global_bounds(shape_node.as_ref()))
+        .expect("Failed to get shape bounds")
 }
@@ -0,0 +1,65 @@
+use gdnative::api::JavaScript;
+use gdnative::prelude::*;
+
+use crate::util;
+use tiler_core::save::{PuzzleProgress, SaveData, Settings};
+
+/// The `localStorage` key used by web builds
+const STORAGE_KEY: &str = "tiler-save";
+/// The save file used by desktop builds
+const SAVE_PATH: &str = "user://save.json";
+
+/// Loads the save data, starting fresh if there isn't any or it can't be read
+pub fn load() -> SaveData {
+    let json = if util::is_web() {
+        JavaScript::godot_singleton()
+            .eval(
+                format!(
+                    "window.localStorage.getItem({})",
+                    util::js_string(STORAGE_KEY)
+                ),
+                true,
+            )
+            .to::<String>()
+    } else {
+        util::read_file(SAVE_PATH)
+    };
+
+    json.and_then(|json| {
+        SaveData::from_json(&json)
+            .map_err(|err| godot_warn!("Ignoring unreadable save data: {}", err))
+            .ok()
+    })
+    .unwrap_or_default()
+}
+
+pub fn store(save: &SaveData) {
+    let json = save.to_json();
+
+    if util::is_web() {
+        JavaScript::godot_singleton().eval(
+            format!(
+                "window.localStorage.setItem({}, {})",
+                util::js_string(STORAGE_KEY),
+                util::js_string(&json)
+            ),
+            true,
+        );
+    } else {
+        util::write_file(SAVE_PATH, &json);
+    }
+}
+
+/// Updates the settings and writes the save straight back
+pub fn update_settings(update: impl FnOnce(&mut Settings)) {
+    let mut save = load();
+    update(&mut save.settings);
+    store(&save);
+}
+
+/// Updates the progress of a single puzzle and writes the save straight back
+pub fn update_progress(shortname: &str, update: impl FnOnce(&mut PuzzleProgress)) {
+    let mut save = load();
+    update(save.progress_mut(shortname));
+    store(&save);
+}
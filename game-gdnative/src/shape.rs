@@ -1,8 +1,12 @@
 use gdnative::{api::*, prelude::*};
 
-use super::tile::{Tile, TileType, TILE_SIDE_LEN, TILE_SIZE};
-use crate::puzzles::ShapeDefinition;
-use crate::util::IVector2;
+use super::tile::{Tile, TileType};
+use crate::grid;
+use crate::puzzle::Puzzle;
+use tiler_core::board::Placement;
+use tiler_core::grid::Grid;
+use tiler_core::orientation::Orientation;
+use tiler_core::puzzles::{Position, PuzzleDefinition, ShapeDefinition};
 
 // Godot Derives
 #[derive(NativeClass, Debug)]
@@ -10,17 +14,69 @@ use crate::util::IVector2;
 #[no_constructor]
 // Serde Derives
 pub struct Shape {
-    drag_pos_start: Option<(Vector2, Vector2)>,
+    shape_idx: usize,
+    grid: Grid,
+
+    drag: Option<Drag>,
+    /// A second finger on the shape while a finger drags it
+    twist: Option<Twist>,
+    drag_start_state: Option<ShapeState>,
+    /// Whether the shape has been picked up with the keyboard or a gamepad. Like a drag, its
+    /// moves are recorded once it's put down.
+    selected: bool,
 
     tiles: Vec<Instance<Tile>>,
+    /// Tile positions as defined, before `orientation` is applied
+    tile_positions: Vec<Position>,
+
+    orientation: Orientation,
+    can_rotate: bool,
+    can_flip: bool,
 
     pos: Vector2,
 }
 
-pub const GLOBAL_GRID_SNAP: Vector2 = Vector2 {
-    x: TILE_SIDE_LEN / 2.0,
-    y: TILE_SIDE_LEN / 2.0,
-};
+/// Lifting a second finger within this many milliseconds of putting it down counts as a tap
+const TAP_MSEC: i64 = 300;
+
+/// A drag in progress
+#[derive(Debug, Copy, Clone)]
+struct Drag {
+    /// Where the shape was when the drag started
+    shape_start: Vector2,
+    /// Where the pointer was when the drag started
+    pointer_start: Vector2,
+    /// The finger dragging the shape and where it is now, or `None` for the mouse, which is
+    /// polled instead
+    finger: Option<(i64, Vector2)>,
+}
+
+/// A second finger put down on a shape being dragged by a finger. Tapping it rotates the shape
+/// clockwise, and twisting it around the first finger rotates the shape the same way.
+#[derive(Debug, Copy, Clone)]
+struct Twist {
+    finger: i64,
+    /// When the finger was put down, from `OS::get_ticks_msec`
+    started: i64,
+    /// The direction from the dragging finger to this one when the shape last turned
+    direction: Vector2,
+    /// Whether twisting has turned the shape, which stops lifting the finger counting as a tap
+    turned: bool,
+}
+
+/// Where a shape is and which way it's turned
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ShapeState {
+    pub position: Vector2,
+    pub orientation: Orientation,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ShapeMove {
+    pub shape_idx: usize,
+    pub from: ShapeState,
+    pub to: ShapeState,
+}
 
 #[methods]
 impl Shape {
@@ -31,77 +87,399 @@ impl Shape {
 
     #[method]
     fn _process(&self, #[base] base: &Node2D, _delta: f64) {
-        if let Some((self_start_pos, mouse_start_pos)) = self.drag_pos_start {
-            let viewport = unsafe { base.get_viewport().unwrap().assume_safe() };
+        if let Some(drag) = self.drag {
+            let pointer_pos = match drag.finger {
+                Some((_, finger_pos)) => finger_pos,
+                None => unsafe { base.get_viewport().unwrap().assume_safe() }.get_mouse_position(),
+            };
 
-            let mouse_diff = viewport.get_mouse_position() - mouse_start_pos;
+            let pointer_diff = pointer_pos - drag.pointer_start;
 
-            base.set_global_position((self_start_pos + mouse_diff).snapped(GLOBAL_GRID_SNAP));
+            base.set_global_position(grid::snap(self.grid, drag.shape_start + pointer_diff));
+        }
+    }
+
+    #[method]
+    fn _unhandled_input(&mut self, #[base] base: &Node2D, event: Ref<InputEvent>) {
+        // Fingers can move and lift anywhere, not just over the shape's tiles
+        let handled = if let Some(touch) = event.clone().cast::<InputEventScreenTouch>() {
+            let touch = unsafe { touch.assume_safe() };
+            !touch.is_pressed() && self.finger_lifted(base, touch.index())
+        } else if let Some(drag) = event.clone().cast::<InputEventScreenDrag>() {
+            let drag = unsafe { drag.assume_safe() };
+            self.finger_moved(base, drag.index(), drag.position())
+        } else {
+            false
+        };
+        if handled {
+            unsafe { base.get_tree().unwrap().assume_safe() }.set_input_as_handled();
+            return;
+        }
+
+        // Keyboard controls only apply to the shape being dragged or selected
+        if !self.is_held() {
+            return;
+        }
+
+        let event = unsafe { event.assume_safe() };
+        if event.is_action_pressed("rotate_piece", false, false) {
+            self.rotate(base, true);
+        } else if event.is_action_pressed("flip_piece", false, false) {
+            self.flip(base);
         }
     }
 }
 
 impl Shape {
     pub fn update_dragged(&mut self, base: &Node2D, event: &InputEventMouseButton) {
-        if event.is_pressed() {
-            self.drag_pos_start = Some((base.global_position(), event.position()))
-        } else {
-            self.drag_pos_start = None;
-            self.pos = base.position();
+        match self.drag {
+            None if event.is_pressed() => self.start_drag(base, event.position(), None),
+            Some(Drag { finger: None, .. }) if !event.is_pressed() => self.end_drag(base),
+            _ => {}
         }
     }
 
-    /// Creates a shape with its top-left cell located at `tl_position`
-    /// Returns the shape and its size in raw tile units
-    pub fn from_definition(
-        tl_position: Vector2,
-        definition: &ShapeDefinition,
-        color: Color,
-    ) -> (Instance<Self, Unique>, Vector2) {
-        let (top_left, bottom_right) = definition.get_tiles().iter().fold(
-            (
-                Vector2::new(f32::INFINITY, f32::INFINITY),
-                Vector2::new(f32::NEG_INFINITY, f32::NEG_INFINITY),
-            ),
-            |(top_left, bottom_right), tile| {
-                (
-                    Vector2 {
-                        x: f32::min(top_left.x, tile.pos.0 as f32 - 0.5),
-                        y: f32::min(top_left.y, tile.pos.1 as f32 - 0.5),
-                    },
-                    Vector2 {
-                        x: f32::max(bottom_right.x, tile.pos.0 as f32 + 0.5),
-                        y: f32::max(bottom_right.y, tile.pos.1 as f32 + 0.5),
-                    },
-                )
-            },
-        );
+    /// Starts dragging the shape with a finger put down on one of its tiles, or starts a twist
+    /// if another finger is already dragging it. Lifting and moving fingers is handled in
+    /// `_unhandled_input`, since they can go anywhere.
+    pub fn touch(&mut self, base: &Node2D, event: &InputEventScreenTouch) {
+        if !event.is_pressed() {
+            return;
+        }
+
+        let finger = event.index();
+        match self.drag {
+            None => {
+                if with_puzzle(base, |puzzle| puzzle.claim_finger(finger)) {
+                    self.start_drag(base, event.position(), Some(finger));
+                }
+            }
+            Some(Drag {
+                finger: Some((_, dragging_pos)),
+                ..
+            }) if self.twist.is_none() => {
+                if with_puzzle(base, |puzzle| puzzle.claim_finger(finger)) {
+                    self.twist = Some(Twist {
+                        finger,
+                        started: OS::godot_singleton().get_ticks_msec(),
+                        direction: event.position() - dragging_pos,
+                        turned: false,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn start_drag(&mut self, base: &Node2D, pointer_pos: Vector2, finger: Option<i64>) {
+        self.clear_highlights();
+        self.drag = Some(Drag {
+            shape_start: base.global_position(),
+            pointer_start: pointer_pos,
+            finger: finger.map(|finger| (finger, pointer_pos)),
+        });
+        // A selected shape keeps the state it was picked up in
+        if self.drag_start_state.is_none() {
+            self.drag_start_state = Some(self.state(base));
+        }
+    }
+
+    fn end_drag(&mut self, base: &Node2D) {
+        self.drag = None;
+        self.pos = base.position();
 
-        let pos = match definition.pos {
-            Some(position) => {
-                let win_center = OS::godot_singleton().window_size() / 2.0;
-                let shape_rel_offset = (bottom_right - top_left) / 2.0;
+        // A selected shape is still held until it's put down
+        if self.selected {
+            return;
+        }
 
-                win_center + (Vector2::from(position) - shape_rel_offset) * TILE_SIZE
+        if let Some(from) = self.drag_start_state.take() {
+            self.finish_move(base, from);
+        }
+        with_puzzle(base, Puzzle::shape_dropped);
+    }
+
+    /// Follows a finger dragging or twisting the shape. Returns whether it was one of them.
+    fn finger_moved(&mut self, base: &Node2D, finger: i64, finger_pos: Vector2) -> bool {
+        let dragging_pos = match self.drag.as_mut().and_then(|drag| drag.finger.as_mut()) {
+            Some((dragging, dragging_pos)) if *dragging == finger => {
+                *dragging_pos = finger_pos;
+                return true;
             }
-            None => tl_position - top_left * TILE_SIZE,
+            Some((_, dragging_pos)) => *dragging_pos,
+            None => return false,
+        };
+
+        let mut twist = match self.twist {
+            Some(twist) if twist.finger == finger => twist,
+            _ => return false,
         };
 
+        // Turn once the fingers are twisted half way to the next orientation, and again each
+        // time they twist a whole turn further
+        let turn_angle = std::f32::consts::TAU / self.grid.turns() as f32;
+        let direction = finger_pos - dragging_pos;
+        let twisted = twist.direction.angle_to(direction);
+        if twisted.abs() > turn_angle / 2.0 {
+            // Angles go clockwise on screen, since y points down
+            let clockwise = twisted > 0.0;
+            self.rotate(base, clockwise);
+            twist.direction =
+                twist
+                    .direction
+                    .rotated(if clockwise { turn_angle } else { -turn_angle });
+            twist.turned = true;
+        }
+        self.twist = Some(twist);
+
+        true
+    }
+
+    /// Handles a finger dragging or twisting the shape being lifted. Returns whether it was one
+    /// of them.
+    fn finger_lifted(&mut self, base: &Node2D, finger: i64) -> bool {
+        match (self.drag.and_then(|drag| drag.finger), self.twist) {
+            (_, Some(twist)) if twist.finger == finger => {
+                self.twist = None;
+                with_puzzle(base, |puzzle| puzzle.release_finger(finger));
+
+                let held_for = OS::godot_singleton().get_ticks_msec() - twist.started;
+                if !twist.turned && held_for < TAP_MSEC {
+                    self.rotate(base, true);
+                }
+
+                true
+            }
+            (Some((dragging, _)), twist) if dragging == finger => {
+                // Lifting the dragging finger ends the twist too
+                self.twist = None;
+                with_puzzle(base, |puzzle| {
+                    puzzle.release_finger(finger);
+                    if let Some(twist) = twist {
+                        puzzle.release_finger(twist.finger);
+                    }
+                });
+                self.end_drag(base);
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Picks the shape up, so it can be moved with the keyboard or a gamepad
+    pub fn select(&mut self, base: &Node2D) {
+        self.clear_highlights();
+        self.selected = true;
+        if self.drag_start_state.is_none() {
+            self.drag_start_state = Some(self.state(base));
+        }
+        self.set_outline(true);
+    }
+
+    /// Puts the shape down, returning the move it made while it was selected. Unlike a drag,
+    /// it's up to the caller to record it, since the puzzle is the one putting it down.
+    pub fn deselect(&mut self, base: &Node2D) -> Option<ShapeMove> {
+        self.selected = false;
+        self.set_outline(false);
+
+        // Still being dragged, so the drag records the move when it ends
+        if self.drag.is_some() {
+            return None;
+        }
+
+        let from = self.drag_start_state.take()?;
+        let to = self.state(base);
+        (from != to).then_some(ShapeMove {
+            shape_idx: self.shape_idx,
+            from,
+            to,
+        })
+    }
+
+    /// Moves the shape one snap step in `direction`
+    pub fn nudge(&mut self, base: &Node2D, direction: Vector2) {
+        let new_pos = grid::snap(
+            self.grid,
+            base.global_position() + grid::nudge(self.grid, direction),
+        );
+
+        // Keep an in-progress drag from snapping the shape back
+        if let Some(drag) = self.drag.as_mut() {
+            drag.shape_start += new_pos - base.global_position();
+        }
+
+        base.set_global_position(new_pos);
+        self.pos = base.position();
+    }
+
+    fn is_held(&self) -> bool {
+        self.drag.is_some() || self.selected
+    }
+
+    fn set_outline(&self, selected: bool) {
+        self.tiles.iter().for_each(|tile| {
+            unsafe { tile.assume_safe() }
+                .map(|tile, _| tile.set_selected(selected))
+                .expect("Failed to outline tile")
+        });
+    }
+
+    pub fn state(&self, base: &Node2D) -> ShapeState {
+        ShapeState {
+            position: base.global_position(),
+            orientation: self.orientation,
+        }
+    }
+
+    /// Puts the shape back into `state` without recording a move
+    pub fn set_state(&mut self, base: &Node2D, state: ShapeState) {
+        self.set_orientation(state.orientation);
+        base.set_global_position(state.position);
+        self.pos = base.position();
+    }
+
+    /// Records the move from `from` in the puzzle's history. Moves made while dragging or
+    /// selected are recorded once the shape is dropped.
+    fn finish_move(&self, base: &Node2D, from: ShapeState) {
+        let to = self.state(base);
+        if self.is_held() || from == to {
+            return;
+        }
+
+        with_puzzle(base, |puzzle| {
+            puzzle.record(vec![ShapeMove {
+                shape_idx: self.shape_idx,
+                from,
+                to,
+            }])
+        });
+    }
+
+    pub fn highlight_tile(&self, tile_idx: usize, highlight: Color) {
+        unsafe { self.tiles[tile_idx].assume_safe() }
+            .map(|tile, _| tile.set_highlight(Some(highlight)))
+            .expect("Failed to highlight tile");
+    }
+
+    /// Highlights every tile of the shape
+    pub fn highlight(&self, highlight: Color) {
+        (0..self.tiles.len()).for_each(|tile_idx| self.highlight_tile(tile_idx, highlight));
+    }
+
+    pub fn set_color(&self, color: Color) {
+        self.tiles.iter().for_each(|tile| {
+            unsafe { tile.assume_safe() }
+                .map_mut(|tile, _| tile.set_shape_color(color))
+                .expect("Failed to color tile")
+        });
+    }
+
+    /// Shows or hides the glyph on every tile
+    pub fn set_glyphs(&self, visible: bool) {
+        self.tiles.iter().for_each(|tile| {
+            unsafe { tile.assume_safe() }
+                .map(|tile, _| tile.set_glyph_visible(visible))
+                .expect("Failed to show tile glyph")
+        });
+    }
+
+    pub fn clear_highlights(&self) {
+        self.tiles.iter().for_each(|tile| {
+            unsafe { tile.assume_safe() }
+                .map(|tile, _| tile.set_highlight(None))
+                .expect("Failed to clear tile highlight")
+        });
+    }
+
+    /// Rotates the shape one step of its grid, if the puzzle allows it
+    pub fn rotate(&mut self, base: &Node2D, clockwise: bool) {
+        if self.can_rotate {
+            let from = self.state(base);
+            self.reorient(base, self.orientation.rotated(self.grid, clockwise));
+            self.finish_move(base, from);
+        }
+    }
+
+    /// Mirrors the shape horizontally, if the puzzle allows it
+    pub fn flip(&mut self, base: &Node2D) {
+        if self.can_flip {
+            let from = self.state(base);
+            self.reorient(base, self.orientation.mirrored(self.grid));
+            self.finish_move(base, from);
+        }
+    }
+
+    /// Moves every tile into `orientation`, keeping the shape centered where it was
+    fn reorient(&mut self, base: &Node2D, orientation: Orientation) {
+        let old_centre = self.centre();
+        self.set_orientation(orientation);
+        let new_centre = self.centre();
+
+        let new_pos = grid::snap(self.grid, base.global_position() + old_centre - new_centre);
+
+        // Keep an in-progress drag from snapping the shape back
+        if let Some(drag) = self.drag.as_mut() {
+            drag.shape_start += new_pos - base.global_position();
+        }
+
+        base.set_global_position(new_pos);
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+
+        let positions = orientation.apply(self.grid, &self.tile_positions);
+        for (tile, pos) in self.tiles.iter().zip(positions.iter()) {
+            unsafe { tile.assume_safe() }
+                .map_mut(|tile, tile_node| tile.set_pos(tile_node.as_ref(), *pos))
+                .expect("Failed to move tile");
+        }
+    }
+
+    /// Creates a shape from its definition, starting at `position`
+    pub fn from_definition(
+        position: Vector2,
+        shape_idx: usize,
+        definition: &ShapeDefinition,
+        puzzle: &PuzzleDefinition,
+        color: Color,
+    ) -> Instance<Self, Unique> {
+        let grid = puzzle.grid;
+        let tile_positions = definition
+            .get_tiles()
+            .iter()
+            .map(|tile_def| tile_def.pos)
+            .collect::<Vec<_>>();
+
         let instance = Self {
             tiles: definition
                 .get_tiles()
                 .iter()
                 .map(|tile_def| {
                     Tile::from_definition(
+                        grid,
                         tile_def,
                         TileType::from_interactable(definition.interactable),
                         color,
+                        shape_idx,
+                        definition.label.as_deref(),
                     )
                 })
                 .map(Instance::into_shared)
                 .collect(),
-            drag_pos_start: None,
-            pos,
+            tile_positions,
+            orientation: Orientation::default(),
+            can_rotate: definition.can_rotate(puzzle),
+            can_flip: definition.can_flip(puzzle),
+            shape_idx,
+            grid,
+            drag: None,
+            twist: None,
+            drag_start_state: None,
+            selected: false,
+            // Keep shapes on the points pieces snap to, so they can line up with the board
+            pos: grid::snap(grid, position),
         }
         .emplace();
 
@@ -115,34 +493,61 @@ impl Shape {
             })
             .unwrap();
 
-        (instance, bottom_right - top_left)
+        instance
     }
 
-    pub fn validate(&self, _base: TRef<Node2D>, all_shapes: &[Instance<Shape>]) -> bool {
-        // TODO: tell the tile which shape it's in
-
-        self.tiles.iter().all(|tile| {
-            unsafe { tile.assume_safe() }
-                .map(|tile, tile_node| tile.validate(tile_node, &all_shapes))
-                .unwrap_or(false)
+    /// Returns where this shape sits on the board, where `origin` is the global position of the
+    /// board's `Cell(0, 0)`. Returns `None` if the shape isn't aligned with the board grid.
+    pub fn placement(&self, base: &Node2D, origin: Vector2, shape_idx: usize) -> Option<Placement> {
+        grid::cell_at(self.grid, base.global_position() - origin).map(|offset| Placement {
+            shape_idx,
+            offset,
+            orientation: self.orientation,
         })
     }
 
-    /// Returns true if any tile in this shape matches the filter function and overlaps with `tgt_tile`
-    pub fn overlaps_with_tile<FilterFunc>(
-        &self,
-        tgt_tile: TInstance<Tile>,
-        filter: FilterFunc,
-    ) -> bool
-    where
-        FilterFunc: FnMut(&TInstance<Tile>) -> bool,
-    {
-        let tgt_loc = IVector2::from(tgt_tile.base().global_position());
+    /// Returns true if the shape can turn and has a tile at the global position `point`
+    pub fn can_turn_at(&self, point: Vector2) -> bool {
+        self.can_rotate
+            && self.tiles.iter().any(|tile| {
+                unsafe { tile.assume_safe() }
+                    .map(|tile, tile_node| tile.has_point(tile_node.as_ref(), point))
+                    .expect("Failed to check tile")
+            })
+    }
 
-        self.tiles
-            .iter()
-            .map(|tile_instance| unsafe { tile_instance.assume_safe() })
-            .filter(filter)
-            .any(|tile| IVector2::from(tile.base().global_position()) == tgt_loc)
+    /// Returns the box around the tiles, in global coordinates
+    pub fn global_bounds(&self, base: &Node2D) -> Rect2 {
+        let (top_left, bottom_right) = grid::bounds(
+            self.grid,
+            &self.orientation.apply(self.grid, &self.tile_positions),
+        );
+
+        Rect2::new(base.global_position() + top_left, bottom_right - top_left)
+    }
+
+    /// Returns the centre of the box around the tiles, relative to the shape
+    fn centre(&self) -> Vector2 {
+        let (top_left, bottom_right) = grid::bounds(
+            self.grid,
+            &self.orientation.apply(self.grid, &self.tile_positions),
+        );
+
+        (top_left + bottom_right) / 2.0
+    }
+}
+
+/// Runs `action` on the puzzle the shape belongs to
+fn with_puzzle<R>(base: &Node2D, action: impl FnOnce(&mut Puzzle) -> R) -> R {
+    unsafe {
+        base.get_parent()
+            .expect("Shape does not have a parent")
+            .assume_safe()
+            .cast::<Node2D>()
+            .unwrap()
     }
+    .cast_instance::<Puzzle>()
+    .expect("Shape's parent is not a puzzle")
+    .map_mut(|puzzle, _| action(puzzle))
+    .expect("Failed to update puzzle")
 }
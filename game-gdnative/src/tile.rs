@@ -2,17 +2,16 @@ use gdnative::api::*;
 use gdnative::prelude::*;
 
 use super::shape::Shape;
-use crate::puzzles::TileDefinition;
-pub use crate::puzzles::TileType;
+use crate::glyph;
+use crate::grid;
+use crate::palette;
 use crate::util;
+use tiler_core::grid::Grid;
+pub use tiler_core::puzzles::TileType;
+use tiler_core::puzzles::{Position, TileDefinition};
 
 pub const TILE_SIDE_LEN: f32 = 50f32;
 
-pub const TILE_SIZE: Vector2 = Vector2 {
-    x: TILE_SIDE_LEN,
-    y: TILE_SIDE_LEN,
-};
-
 const BORDER_SIZE: f32 = 2f32;
 pub const TILE_INNER_SIDE_LEN: f32 = TILE_SIDE_LEN - BORDER_SIZE * 2.0;
 pub const TILE_INNER_OFFSET: Vector2 = Vector2 {
@@ -27,25 +26,55 @@ const TILE_BACKGROUND_COLOR: Color = Color {
     a: 1.0f32,
 };
 
-impl TileType {
-    pub fn from_interactable(interactable: bool) -> Self {
-        if interactable {
-            TileType::Foreground
-        } else {
-            TileType::Background
-        }
+/// Border color of the tiles in the piece selected with the keyboard or a gamepad
+const SELECTED_BORDER_COLOR: Color = Color {
+    r: 1.0f32,
+    g: 1.0f32,
+    b: 1.0f32,
+    a: 1.0f32,
+};
+
+const HIGHLIGHT_WEIGHT: f32 = 0.6f32;
+
+/// The box labels are centred in
+const LABEL_SIZE: Vector2 = Vector2 {
+    x: TILE_SIDE_LEN,
+    y: TILE_SIDE_LEN / 2.0,
+};
+
+/// How far glyphs reach from the centre of their tile
+const GLYPH_RADIUS: f32 = TILE_SIDE_LEN * 0.2;
+
+/// The device Godot gives the mouse events it makes up from touches
+const EMULATED_MOUSE_DEVICE: i64 = -1;
+
+/// Windows smaller than this across either side count as small screens
+const SMALL_SCREEN_SIZE: f32 = 600f32;
+/// How far past their edges tiles can be grabbed on small touch screens, where fingers cover
+/// more than a tile
+const SMALL_SCREEN_HIT_MARGIN: f32 = 8f32;
+
+/// Returns how far past its edges a tile can be grabbed
+fn hit_margin() -> f32 {
+    let os = OS::godot_singleton();
+    let window_size = os.window_size();
+
+    if os.has_touchscreen_ui_hint() && window_size.x.min(window_size.y) < SMALL_SCREEN_SIZE {
+        SMALL_SCREEN_HIT_MARGIN
+    } else {
+        0f32
     }
+}
 
-    fn to_foreground_color(&self, shape_color: Color) -> Color {
-        match self {
-            TileType::Foreground => shape_color,
-            TileType::Background => Color {
-                r: 0.3f32,
-                g: 0.3f32,
-                b: 0.3f32,
-                a: 1.0f32,
-            },
-        }
+fn foreground_color(tile_type: TileType, shape_color: Color) -> Color {
+    match tile_type {
+        TileType::Foreground => shape_color,
+        TileType::Background => Color {
+            r: 0.3f32,
+            g: 0.3f32,
+            b: 0.3f32,
+            a: 1.0f32,
+        },
     }
 }
 
@@ -53,15 +82,25 @@ impl TileType {
 #[inherit(Area2D)]
 #[no_constructor]
 pub struct Tile {
-    pos: Vector2,
+    grid: Grid,
+    pos: Position,
     tile_type: TileType,
+    color: Color,
+    foreground: Ref<Polygon2D>,
+    background: Ref<Polygon2D>,
+    /// Drawn over the foreground, so pieces can be told apart without color
+    glyph: Ref<Polygon2D>,
+    glyph_idx: usize,
+    /// The piece's label, shown in place of the glyph
+    label: Option<Ref<Label>>,
+    collision: Ref<CollisionPolygon2D>,
 }
 
 #[methods]
 impl Tile {
     #[method]
     fn _ready(&self, #[base] base: &Area2D) {
-        base.set_position(self.pos * TILE_SIZE);
+        base.set_position(grid::position_offset(self.grid, self.pos));
     }
 
     #[method]
@@ -72,117 +111,194 @@ impl Tile {
         raw_event: Ref<InputEvent>,
         _shape_idx: i32,
     ) {
-        if let Some(event) = raw_event.clone().cast::<InputEventMouseButton>() {
-            if let TileType::Background = self.tile_type {
-                return;
-            }
+        if let TileType::Background = self.tile_type {
+            return;
+        }
+
+        let parent = unsafe {
+            base.get_parent()
+                .expect("Tile does not have a parent")
+                .assume_safe()
+                .cast::<Node2D>()
+                .unwrap()
+        };
+
+        let parent_instance = parent
+            .cast_instance::<Shape>()
+            .expect("Tile's parent is not a shape");
 
+        if let Some(event) = raw_event.clone().cast::<InputEventScreenTouch>() {
             let event = unsafe { event.assume_safe() };
 
-            let parent = unsafe {
-                base.get_parent()
-                    .expect("Tile does not have a parent")
-                    .assume_safe()
-                    .cast::<Node2D>()
-                    .unwrap()
-            };
+            parent_instance
+                .map_mut(|p, _owner| p.touch(parent.as_ref(), event.as_ref()))
+                .expect("Failed to update shape from touch input")
+        } else if let Some(event) = raw_event.cast::<InputEventMouseButton>() {
+            let event = unsafe { event.assume_safe() };
 
-            let parent_instance = parent
-                .cast_instance::<Shape>()
-                .expect("Tile's parent is not a shape");
+            // Touches are handled as they are, rather than the mouse clicks Godot makes from them
+            if event.device() == EMULATED_MOUSE_DEVICE {
+                return;
+            }
 
             parent_instance
-                .map_mut(|p, _owner| p.update_dragged(parent.as_ref(), event.as_ref()))
-                .expect("Failed to set is_being_dragged")
+                .map_mut(|p, _owner| match event.button_index() {
+                    GlobalConstants::BUTTON_LEFT => {
+                        p.update_dragged(parent.as_ref(), event.as_ref())
+                    }
+                    GlobalConstants::BUTTON_RIGHT if event.is_pressed() && event.shift() => {
+                        p.flip(parent.as_ref())
+                    }
+                    GlobalConstants::BUTTON_RIGHT if event.is_pressed() => {
+                        p.rotate(parent.as_ref(), true)
+                    }
+                    GlobalConstants::BUTTON_WHEEL_UP if event.is_pressed() => {
+                        p.rotate(parent.as_ref(), false)
+                    }
+                    GlobalConstants::BUTTON_WHEEL_DOWN if event.is_pressed() => {
+                        p.rotate(parent.as_ref(), true)
+                    }
+                    _ => {}
+                })
+                .expect("Failed to update shape from mouse input")
         }
     }
 }
 
 impl Tile {
+    /// Tints the tile towards `highlight` to point out a problem with it, or restores its color
+    /// if `highlight` is `None`
+    pub fn set_highlight(&self, highlight: Option<Color>) {
+        let color = match highlight {
+            Some(highlight) => self.color.lerp(highlight, HIGHLIGHT_WEIGHT),
+            None => self.color,
+        };
+
+        unsafe { self.foreground.assume_safe() }.set_color(color);
+    }
+
+    /// Recolors the tile for a piece of `shape_color`, clearing any highlight
+    pub fn set_shape_color(&mut self, shape_color: Color) {
+        self.color = foreground_color(self.tile_type, shape_color);
+
+        unsafe { self.foreground.assume_safe() }.set_color(self.color);
+        unsafe { self.glyph.assume_safe() }.set_color(palette::contrasting(self.color));
+        if let Some(label) = self.label {
+            unsafe { label.assume_safe() }
+                .add_color_override("font_color", palette::contrasting(self.color));
+        }
+    }
+
+    /// Shows or hides the glyph, or the label if the piece has one. Background tiles never show
+    /// either.
+    pub fn set_glyph_visible(&self, visible: bool) {
+        let visible = visible && self.tile_type == TileType::Foreground;
+
+        match self.label {
+            Some(label) => unsafe { label.assume_safe() }.set_visible(visible),
+            None => unsafe { self.glyph.assume_safe() }.set_visible(visible),
+        }
+    }
+
+    /// Outlines the tile while its piece is selected
+    pub fn set_selected(&self, selected: bool) {
+        let color = if selected {
+            SELECTED_BORDER_COLOR
+        } else {
+            TILE_BACKGROUND_COLOR
+        };
+
+        unsafe { self.background.assume_safe() }.set_color(color);
+    }
+
+    /// Returns true if the global position `point` is within the area the tile can be grabbed by
+    pub fn has_point(&self, base: &Area2D, point: Vector2) -> bool {
+        let hit_area = unsafe { self.collision.assume_safe() }.polygon();
+
+        Geometry::godot_singleton().is_point_in_polygon(base.to_local(point), hit_area)
+    }
+
+    pub fn set_pos(&mut self, base: &Area2D, pos: Position) {
+        self.pos = pos;
+        base.set_position(grid::position_offset(self.grid, pos));
+        self.update_polygons();
+    }
+
+    /// Reshapes the tile to fit its cell. Only triangles need this, since moving them to
+    /// another cell can turn them upside down.
+    fn update_polygons(&self) {
+        let outline = grid::cell_polygon(self.grid, self.pos, 0.0);
+        let inner = grid::cell_polygon(self.grid, self.pos, BORDER_SIZE);
+        let hit_area = grid::cell_polygon(self.grid, self.pos, -hit_margin());
+        let centre = grid::cell_centre(self.grid, self.pos);
+        let glyph = glyph::glyph(self.glyph_idx, GLYPH_RADIUS)
+            .into_iter()
+            .map(|point| centre + point)
+            .collect();
+
+        unsafe { self.background.assume_safe() }.set_polygon(PoolArray::from_vec(outline));
+        unsafe { self.foreground.assume_safe() }.set_polygon(PoolArray::from_vec(inner));
+        unsafe { self.collision.assume_safe() }.set_polygon(PoolArray::from_vec(hit_area));
+        unsafe { self.glyph.assume_safe() }.set_polygon(PoolArray::from_vec(glyph));
+        if let Some(label) = self.label {
+            unsafe { label.assume_safe() }.set_position(centre - LABEL_SIZE / 2.0, false);
+        }
+    }
+
     pub fn from_definition(
+        grid: Grid,
         definition: &TileDefinition,
         base_type: TileType,
         shape_color: Color,
+        glyph_idx: usize,
+        label: Option<&str>,
     ) -> Instance<Self, Unique> {
         let tile_type = definition.tile_type.unwrap_or(base_type);
+        let color = foreground_color(tile_type, shape_color);
+
+        let bg = util::create_polygon(&[], TILE_BACKGROUND_COLOR).into_shared();
+        let fg = util::create_polygon(&[], color).into_shared();
+        let glyph = util::create_polygon(&[], palette::contrasting(color));
+        glyph.set_visible(false);
+        let glyph = glyph.into_shared();
+        let label = label.map(|text| {
+            let label = Label::new();
+            label.set_text(text);
+            label.set_align(Label::ALIGN_CENTER);
+            label.set_valign(Label::VALIGN_CENTER);
+            label.set_size(LABEL_SIZE, false);
+            label.set_mouse_filter(Control::MOUSE_FILTER_IGNORE);
+            label.add_color_override("font_color", palette::contrasting(color));
+            label.set_visible(false);
+            label.into_shared()
+        });
+        let collision = CollisionPolygon2D::new().into_shared();
 
         let instance = Self {
-            pos: Vector2::from(definition.pos),
+            grid,
+            pos: definition.pos,
             tile_type,
+            color,
+            foreground: fg,
+            background: bg,
+            glyph,
+            glyph_idx,
+            label,
+            collision,
         }
         .emplace();
 
-        let bg = util::create_square(TILE_SIDE_LEN, TILE_BACKGROUND_COLOR);
         instance.base().add_child(bg, false);
-
-        let fg = util::create_square(
-            TILE_INNER_SIDE_LEN,
-            tile_type.to_foreground_color(shape_color),
-        )
-        .into_shared();
         instance.base().add_child(fg, false);
-        unsafe { fg.assume_safe() }.set_position(TILE_INNER_OFFSET);
-
-        let collision_shape = CollisionShape2D::new();
-        let rectangle = RectangleShape2D::new();
-        rectangle.set_extents(TILE_SIZE / 2.0);
-        collision_shape.set_shape(rectangle);
-        collision_shape.set_position(TILE_SIZE / 2.0);
-        instance.base().add_child(collision_shape, false);
-
-        instance
-    }
-
-    pub fn validate(&self, base: TRef<Area2D>, all_shapes: &[Instance<Shape>]) -> bool {
-        let instance = base.cast_instance().unwrap();
-
-        match self.tile_type {
-            TileType::Background => self.validate_background(instance, all_shapes),
-            TileType::Foreground => self.validate_foreground(instance, all_shapes),
+        instance.base().add_child(glyph, false);
+        if let Some(label) = label {
+            instance.base().add_child(label, false);
         }
-    }
-
-    fn validate_background(
-        &self,
-        instance: TInstance<Self>,
-        all_shapes: &[Instance<Shape>],
-    ) -> bool {
-        // I am a background tile
-        // I want to confirm that any one foreground tile shares my position
-        // TODO: do i only want a single tile here?
-
-        all_shapes.iter().any(|shape| {
-            unsafe { shape.assume_safe() }
-                .map(|shape, _| {
-                    shape.overlaps_with_tile(instance.clone(), |tile_inst| {
-                        tile_inst
-                            .map(|tile, _| tile.tile_type == TileType::Foreground)
-                            .unwrap_or(false)
-                    })
-                })
-                .unwrap_or(false)
-        })
-    }
+        instance.base().add_child(collision, false);
+        instance
+            .map(|tile, _| tile.update_polygons())
+            .expect("Failed to shape tile");
 
-    fn validate_foreground(
-        &self,
-        instance: TInstance<Self>,
-        all_shapes: &[Instance<Shape>],
-    ) -> bool {
-        // I am a foreground tile
-        // I want to confirm that any one background tile shares my position
-        // TODO: do i only want a single tile here?
-
-        all_shapes.iter().any(|shape| {
-            unsafe { shape.assume_safe() }
-                .map(|shape, _| {
-                    shape.overlaps_with_tile(instance.clone(), |tile_inst| {
-                        tile_inst
-                            .map(|tile, _| tile.tile_type == TileType::Background)
-                            .unwrap_or(false)
-                    })
-                })
-                .unwrap_or(false)
-        })
+        instance
     }
 }
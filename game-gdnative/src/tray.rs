@@ -0,0 +1,147 @@
+use gdnative::prelude::*;
+
+use crate::grid;
+use crate::tile::TILE_SIDE_LEN;
+use tiler_core::grid::Grid;
+use tiler_core::puzzles::{Position, PuzzleDefinition, ShapeDefinition};
+
+/// The space left between pieces, and around the board and the buttons
+const GAP: f32 = TILE_SIDE_LEN / 2.0;
+
+/// Where the controls drawn over the puzzle are, which pieces are kept clear of
+#[derive(Debug, Copy, Clone)]
+pub struct Overlay {
+    /// The buttons along the bottom of the window
+    pub buttons: Rect2,
+    /// The settings in the top right corner of the window
+    pub settings: Rect2,
+}
+
+/// Returns where each shape in `puzzle` starts, in the same order as its shapes.
+///
+/// Shapes with a position start there, and the board starts at `Position(0, 0)` if it doesn't
+/// have one. They keep their places relative to each other, with the box around all of them in
+/// the middle of the window. The other pieces are packed around them, leaving the buttons clear.
+/// Pieces which don't fit in the window are put below it, and the puzzle scrolls down to them.
+pub fn start_positions(
+    puzzle: &PuzzleDefinition,
+    window: Vector2,
+    overlay: Overlay,
+) -> Vec<Vector2> {
+    let grid = puzzle.grid;
+    let boxes = puzzle
+        .shapes
+        .iter()
+        .map(|shape_def| tile_box(grid, shape_def))
+        .collect::<Vec<_>>();
+
+    let offsets = puzzle
+        .shapes
+        .iter()
+        .map(|shape_def| {
+            let pos = shape_def
+                .pos
+                .or_else(|| (!shape_def.interactable).then_some(Position(0, 0)))?;
+
+            Some(grid::position_offset(grid, pos))
+        })
+        .collect::<Vec<_>>();
+
+    let placed_box = offsets
+        .iter()
+        .zip(boxes.iter())
+        .filter_map(|(offset, tile_box)| offset.map(|offset| moved(*tile_box, offset)))
+        .reduce(|a, b| a.merge(b));
+    let origin = placed_box.map_or(Vector2::ZERO, |placed_box| {
+        grid::snap(
+            grid,
+            window / 2.0 - placed_box.position - placed_box.size / 2.0,
+        )
+    });
+
+    let mut positions = offsets
+        .into_iter()
+        .map(|offset| offset.map(|offset| origin + offset))
+        .collect::<Vec<_>>();
+
+    let mut taken = positions
+        .iter()
+        .zip(boxes.iter())
+        .filter_map(|(pos, tile_box)| pos.map(|pos| moved(*tile_box, pos)))
+        .chain([overlay.buttons, overlay.settings])
+        .collect::<Vec<_>>();
+
+    // Bigger pieces are the hardest to fit, so they go first and the smaller ones fill the
+    // gaps they leave
+    let mut loose = (0..positions.len())
+        .filter(|shape_idx| positions[*shape_idx].is_none())
+        .collect::<Vec<_>>();
+    loose.sort_by(|a, b| {
+        let (a, b) = (boxes[*a].size, boxes[*b].size);
+        (b.x * b.y, b.y).partial_cmp(&(a.x * a.y, a.y)).unwrap()
+    });
+
+    for shape_idx in loose {
+        let pos = first_space(grid, window.x, &taken, boxes[shape_idx]);
+        taken.push(moved(boxes[shape_idx], pos));
+        positions[shape_idx] = Some(pos);
+    }
+
+    positions.into_iter().map(Option::unwrap).collect()
+}
+
+/// Returns how far the window can scroll down to show everything down to `bottom`, without
+/// leaving it under the buttons
+pub fn scroll_limit(bottom: f32, overlay: Overlay) -> f32 {
+    f32::max(bottom + GAP - overlay.buttons.position.y, 0.0)
+}
+
+/// Returns the scroll nearest to `scroll` which shows the whole of `rect` above the buttons
+pub fn scroll_to_show(scroll: f32, rect: Rect2, overlay: Overlay) -> f32 {
+    let top = rect.position.y - GAP;
+    let bottom = rect.end().y + GAP - overlay.buttons.position.y;
+
+    scroll.min(top).max(bottom)
+}
+
+/// Returns the first position, scanning across a row of snap points at a time, where a shape
+/// with tiles in `tile_box` is clear of everything in `taken` and fits across the window.
+/// There's always space further down.
+fn first_space(grid: Grid, width: f32, taken: &[Rect2], tile_box: Rect2) -> Vector2 {
+    let step = grid::step(grid);
+    // Pieces wider than the window still go down its left side
+    let right = f32::max(width, tile_box.size.x + GAP * 2.0 + step.x * 2.0) - GAP;
+
+    (0..)
+        .flat_map(|row| {
+            (0..(right / step.x) as i32).map(move |col| {
+                let corner = Vector2::new(col as f32, row as f32) * step;
+                grid::snap(grid, corner - tile_box.position)
+            })
+        })
+        .find(|pos| {
+            let placed = moved(tile_box, *pos);
+
+            placed.position.x >= GAP
+                && placed.position.y >= GAP
+                && placed.end().x <= right
+                && !taken.iter().any(|rect| placed.grow(GAP).intersects(*rect))
+        })
+        .unwrap()
+}
+
+/// Returns the box around a shape's tiles as defined, relative to the shape
+fn tile_box(grid: Grid, shape_def: &ShapeDefinition) -> Rect2 {
+    let tile_positions = shape_def
+        .get_tiles()
+        .iter()
+        .map(|tile_def| tile_def.pos)
+        .collect::<Vec<_>>();
+    let (top_left, bottom_right) = grid::bounds(grid, &tile_positions);
+
+    Rect2::new(top_left, bottom_right - top_left)
+}
+
+fn moved(rect: Rect2, by: Vector2) -> Rect2 {
+    Rect2::new(rect.position + by, rect.size)
+}
@@ -1,41 +1,85 @@
-use gdnative::{api::MenuButton, prelude::*};
+use gdnative::{
+    api::{Label, PopupPanel, ScrollContainer, VBoxContainer},
+    prelude::*,
+};
 
-use crate::{puzzle::Puzzle, puzzles::PuzzleDefinition};
+use crate::packs::{CATALOG, RECENTLY_EDITED};
+use crate::save;
+use tiler_core::chapters::{self, Chapter};
+use tiler_core::save::SaveData;
 
-include!(concat!(env!("OUT_DIR"), "/puzzle_definitions.rs"));
+const LEVEL_SELECT_SIZE: Vector2 = Vector2 { x: 400.0, y: 600.0 };
+/// How many puzzles the developer-only "Recently edited" list shows
+const RECENTLY_EDITED_SHOWN: usize = 5;
 
 #[derive(NativeClass)]
 #[inherit(CanvasLayer)]
 #[register_with(Self::register)]
-pub struct UI;
+pub struct UI {
+    level_select: Option<Ref<PopupPanel>>,
+    level_list: Option<Ref<ScrollContainer>>,
+}
 
 #[methods]
 impl UI {
     #[method]
-    fn _ready(&self, #[base] base: TRef<CanvasLayer>) {
-        let popup_menu = unsafe {
+    fn _ready(&mut self, #[base] base: TRef<CanvasLayer>) {
+        unsafe {
             base.get_node("LevelSelectButton")
                 .expect("UI layer does not have a level select button")
                 .assume_safe()
-                .cast::<MenuButton>()
-                .expect("LevelSelectButton is not a MenuButton")
-                .get_popup()
-                .expect("LevelSelectButton does not have a popup")
-                .assume_safe()
-        };
+                .cast::<Button>()
+                .expect("LevelSelectButton is not a Button")
+        }
+        .connect(
+            "pressed",
+            base,
+            "_on_level_select_pressed",
+            VariantArray::new_shared(),
+            0,
+        )
+        .expect("Failed to connect to pressed signal on LevelSelectButton");
+
+        let level_list = ScrollContainer::new();
+        level_list.set_enable_h_scroll(false);
+        let level_list = level_list.into_shared();
 
-        PUZZLES
-            .into_iter()
-            .filter_map(|pdef| serde_yaml::from_str::<PuzzleDefinition>(pdef).ok())
-            .for_each(|puzzle| {
-                popup_menu.add_item(puzzle.name, 0, 0);
-            });
+        let level_select = PopupPanel::new();
+        level_select.add_child(level_list, false);
+        let level_select = level_select.into_shared();
+        base.add_child(level_select, false);
+
+        self.level_select = Some(level_select);
+        self.level_list = Some(level_list);
+    }
+
+    /// Opens the level select, refreshed with the latest progress
+    #[method]
+    fn _on_level_select_pressed(&self, #[base] base: TRef<CanvasLayer>) {
+        let level_list = unsafe { self.level_list.unwrap().assume_safe() };
+        for child in level_list.get_children().iter() {
+            if let Some(child) = child.to_object::<Node>() {
+                unsafe { child.assume_safe() }.queue_free();
+            }
+        }
+        level_list.add_child(self.levels(base), false);
+
+        unsafe { self.level_select.unwrap().assume_safe() }.popup_centered(LEVEL_SELECT_SIZE);
+    }
+
+    #[method]
+    fn _on_level_pressed(&self, #[base] base: &CanvasLayer, puzzle_idx: i64) {
+        unsafe { self.level_select.unwrap().assume_safe() }.hide();
+        base.emit_signal("puzzle_selected", &[puzzle_idx.to_variant()]);
     }
 }
 
 impl UI {
     fn new(_base: &CanvasLayer) -> Self {
-        UI
+        UI {
+            level_select: None,
+            level_list: None,
+        }
     }
 
     fn register(builder: &ClassBuilder<Self>) {
@@ -44,4 +88,89 @@ impl UI {
             .with_param("puzzle_idx", VariantType::I64)
             .done();
     }
+
+    /// Lists every chapter and its puzzles. Puzzles in locked chapters can be seen but not
+    /// picked.
+    fn levels(&self, base: TRef<CanvasLayer>) -> Ref<VBoxContainer, Unique> {
+        let save = save::load();
+        let chapters = chapters::chapters(&CATALOG);
+        let unlocked = chapters::unlocked_count(&chapters, &CATALOG, &save);
+
+        let levels = VBoxContainer::new();
+        levels.set_h_size_flags(Control::SIZE_EXPAND_FILL);
+
+        // Only filled in for builds with TILER_RECENT_FIRST set
+        if !RECENTLY_EDITED.is_empty() {
+            let heading = Label::new();
+            heading.set_text("Recently edited");
+            levels.add_child(heading, false);
+
+            for puzzle_idx in RECENTLY_EDITED.iter().take(RECENTLY_EDITED_SHOWN) {
+                levels.add_child(self.level_button(base, &save, *puzzle_idx, false), false);
+            }
+        }
+
+        for (chapter_idx, chapter) in chapters.iter().enumerate() {
+            let locked = chapter_idx >= unlocked;
+
+            let heading = Label::new();
+            heading.set_text(chapter_heading(chapter, &save, locked));
+            levels.add_child(heading, false);
+
+            for puzzle_idx in &chapter.puzzles {
+                levels.add_child(self.level_button(base, &save, *puzzle_idx, locked), false);
+            }
+        }
+
+        levels
+    }
+
+    fn level_button(
+        &self,
+        base: TRef<CanvasLayer>,
+        save: &SaveData,
+        puzzle_idx: usize,
+        locked: bool,
+    ) -> Ref<Button, Unique> {
+        let entry = &CATALOG.entries()[puzzle_idx];
+        let marker = if save.is_solved(&entry.shortname) {
+            "[x]"
+        } else {
+            "[ ]"
+        };
+
+        let button = Button::new();
+        let difficulty = entry
+            .difficulty
+            .map(|difficulty| format!(" - {}", difficulty.label()))
+            .unwrap_or_default();
+        button.set_text(format!("{marker} {}{difficulty}", entry.definition.name));
+        button.set_text_align(Button::ALIGN_LEFT);
+        button.set_disabled(locked);
+        button
+            .connect(
+                "pressed",
+                base,
+                "_on_level_pressed",
+                VariantArray::from_iter([puzzle_idx as i64]).into_shared(),
+                0,
+            )
+            .expect("Failed to connect to pressed signal on level button");
+
+        button
+    }
+}
+
+fn chapter_heading(chapter: &Chapter, save: &SaveData, locked: bool) -> String {
+    let solved = chapter
+        .puzzles
+        .iter()
+        .filter(|idx| save.is_solved(&CATALOG.entries()[**idx].shortname))
+        .count();
+
+    if locked {
+        format!("{} (locked)", chapter.title)
+    } else {
+        format!("{} ({solved}/{})", chapter.title, chapter.puzzles.len())
+    }
 }
@@ -1,45 +1,68 @@
 use gdnative::api::*;
 use gdnative::prelude::*;
 
-use crate::puzzles::Position;
+use tiler_core::puzzles::Position;
 
-pub fn screen_center(node: &Node) -> Vector2 {
-    unsafe { node.get_viewport().unwrap().assume_safe().size() / 2.0 }
+/// Returns a query parameter of the page's URL. Always `None` outside of web builds.
+pub fn url_param(name: &str) -> Option<String> {
+    JavaScript::godot_singleton()
+        .eval(
+            format!(
+                "(new URLSearchParams(window.location.search)).get({})",
+                js_string(name)
+            ),
+            true,
+        )
+        .to::<String>()
 }
 
-pub fn create_square(side_len: f32, color: Color) -> Ref<Polygon2D, Unique> {
-    let square = Polygon2D::new();
-    square.set_polygon(PoolArray::from_slice(&[
-        Vector2::new(0.0, 0.0),
-        Vector2::new(side_len, 0.0),
-        Vector2::new(side_len, side_len),
-        Vector2::new(0.0, side_len),
-    ]));
-    square.set_color(color);
-
-    square
+/// Quotes `s` as a JavaScript string literal
+pub fn js_string(s: &str) -> String {
+    serde_json::to_string(s).unwrap()
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub struct IVector2 {
-    x: i16,
-    y: i16,
+/// Reads a whole text file, or returns `None` if it can't be opened
+pub fn read_file(path: &str) -> Option<String> {
+    let file = File::new();
+    file.open(path, File::READ).ok()?;
+
+    let contents = file.get_as_text(false).to_string();
+    file.close();
+
+    Some(contents)
 }
 
-impl From<Vector2> for IVector2 {
-    fn from(vector: Vector2) -> Self {
-        Self {
-            x: vector.x as i16,
-            y: vector.y as i16,
-        }
+/// Writes a whole text file, replacing whatever was there
+pub fn write_file(path: &str, contents: &str) {
+    let file = File::new();
+    if let Err(err) = file.open(path, File::WRITE) {
+        godot_error!("Failed to open {} for writing: {:?}", path, err);
+        return;
     }
+
+    file.store_string(contents);
+    file.close();
+}
+
+pub fn is_web() -> bool {
+    OS::godot_singleton().has_feature("JavaScript")
+}
+
+pub fn screen_center(node: &Node) -> Vector2 {
+    unsafe { node.get_viewport().unwrap().assume_safe().size() / 2.0 }
+}
+
+pub fn create_polygon(points: &[Vector2], color: Color) -> Ref<Polygon2D, Unique> {
+    let polygon = Polygon2D::new();
+    polygon.set_polygon(PoolArray::from_slice(points));
+    polygon.set_color(color);
+
+    polygon
 }
 
-impl From<Position> for Vector2 {
-    fn from(pos: Position) -> Self {
-        Self {
-            x: pos.0 as f32,
-            y: pos.1 as f32,
-        }
+pub fn position_to_vector(pos: Position) -> Vector2 {
+    Vector2 {
+        x: pos.0 as f32,
+        y: pos.1 as f32,
     }
 }
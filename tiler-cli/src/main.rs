@@ -0,0 +1,430 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use tiler_core::board::Board;
+use tiler_core::catalog::{Catalog, PACK_SUFFIX};
+use tiler_core::generator::{self, GenerateError, GeneratorOptions};
+use tiler_core::grid::Grid;
+use tiler_core::lint::{lint, LintError};
+use tiler_core::puzzles::{PuzzleDefinition, PuzzleFormat};
+use tiler_core::render;
+use tiler_core::solver::Solver;
+use walkdir::WalkDir;
+
+/// Checks, solves and draws puzzle files without running the game
+#[derive(Parser)]
+#[command(name = "tiler")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Checks that puzzles parse, have as many piece tiles as board cells and can be solved
+    Lint {
+        /// Puzzle and pack files, or directories to search for them
+        #[arg(default_value = "game-gdnative/src/puzzles")]
+        paths: Vec<PathBuf>,
+    },
+    /// Rates how hard puzzles are and lists them from easiest to hardest
+    Rate {
+        /// Puzzle and pack files, or directories to search for them
+        #[arg(default_value = "game-gdnative/src/puzzles")]
+        paths: Vec<PathBuf>,
+    },
+    /// Prints a solution of a puzzle
+    Solve { file: PathBuf },
+    /// Counts the solutions of a puzzle
+    CountSolutions {
+        file: PathBuf,
+        /// Stop counting once this many solutions have been found
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Draws a puzzle's board and pieces
+    Render { file: PathBuf },
+    /// Converts a puzzle between the YAML and ASCII art formats and prints it
+    Convert {
+        file: PathBuf,
+        /// The format to convert to. Defaults to whichever format the file isn't in.
+        #[arg(long, value_enum)]
+        to: Option<Format>,
+    },
+    /// Makes a random puzzle by cutting a rectangular board into pieces and prints it
+    Generate {
+        /// The same seed and options always make the same puzzle. Defaults to a random seed,
+        /// which is printed so the puzzle can be made again.
+        #[arg(long)]
+        seed: Option<u64>,
+        /// On hex grids the board is a rhombus, and on triangle grids it's a parallelogram
+        /// `width` triangles across
+        #[arg(long, value_enum, default_value_t = GridKind::Square)]
+        grid: GridKind,
+        #[arg(long, default_value_t = 5)]
+        width: usize,
+        #[arg(long, default_value_t = 5)]
+        height: usize,
+        #[arg(long, default_value_t = 3)]
+        min_piece_size: usize,
+        #[arg(long, default_value_t = 5)]
+        max_piece_size: usize,
+        #[arg(long)]
+        allow_rotation: bool,
+        #[arg(long)]
+        allow_flip: bool,
+        /// Keep generating until the puzzle has exactly one solution
+        #[arg(long)]
+        unique: bool,
+        /// How many boards to cut up before giving up
+        #[arg(long, default_value_t = 100)]
+        attempts: usize,
+        #[arg(long, value_enum, default_value_t = Format::Yaml)]
+        to: Format,
+    },
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum Format {
+    Yaml,
+    Ascii,
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+enum GridKind {
+    Square,
+    Hex,
+    Triangle,
+}
+
+impl From<GridKind> for Grid {
+    fn from(kind: GridKind) -> Self {
+        match kind {
+            GridKind::Square => Grid::Square,
+            GridKind::Hex => Grid::Hex,
+            GridKind::Triangle => Grid::Triangle,
+        }
+    }
+}
+
+enum LoadError {
+    Read(std::io::Error),
+    Parse(serde_yaml::Error),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Read(err) => write!(f, "failed to read puzzle: {err}"),
+            LoadError::Parse(err) => write!(f, "failed to parse puzzle: {err}"),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let result = match Cli::parse().command {
+        Command::Lint { paths } => lint_all(&paths),
+        Command::Rate { paths } => rate(&paths),
+        Command::Solve { file } => solve(&file),
+        Command::CountSolutions { file, limit } => count_solutions(&file, limit),
+        Command::Render { file } => render_puzzle(&file),
+        Command::Convert { file, to } => convert(&file, to),
+        Command::Generate {
+            seed,
+            grid,
+            width,
+            height,
+            min_piece_size,
+            max_piece_size,
+            allow_rotation,
+            allow_flip,
+            unique,
+            attempts,
+            to,
+        } => generate(
+            seed,
+            GeneratorOptions {
+                min_piece_size,
+                max_piece_size,
+                allow_rotation,
+                allow_flip,
+                unique_solution: unique,
+                max_attempts: attempts,
+                ..GeneratorOptions::rect_on(grid.into(), width, height)
+            },
+            to,
+        ),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn lint_all(paths: &[PathBuf]) -> Result<(), String> {
+    let mut checked = 0;
+    let mut failed = 0;
+
+    for file in paths
+        .iter()
+        .flat_map(WalkDir::new)
+        .filter_map(|d| d.ok())
+        .filter(|direntry| direntry.file_type().is_file())
+    {
+        let path = file.path();
+
+        if path.to_string_lossy().ends_with(PACK_SUFFIX) {
+            let (pack_checked, pack_failed) = lint_pack(path);
+            checked += pack_checked;
+            failed += pack_failed;
+            continue;
+        }
+
+        let result = match load(path) {
+            Some(Ok((puzzle, _))) => lint(&puzzle),
+            Some(Err(LoadError::Parse(err))) => Err(LintError::Parse(err)),
+            Some(Err(err)) => {
+                checked += 1;
+                failed += 1;
+                eprintln!("error: {}: {err}", path.display());
+                continue;
+            }
+            None => continue,
+        };
+
+        checked += 1;
+        if let Err(err) = result {
+            failed += 1;
+            match err.line() {
+                Some(line) => eprintln!("error: {}:{line}: {err}", path.display()),
+                None => eprintln!("error: {}: {err}", path.display()),
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!(
+            "{failed} of {checked} puzzle(s) failed to validate"
+        ));
+    }
+
+    println!("{checked} puzzle(s) ok");
+    Ok(())
+}
+
+/// Lints every puzzle in a pack file, returning how many were checked and how many failed
+fn lint_pack(path: &Path) -> (usize, usize) {
+    let mut catalog = Catalog::default();
+    let loaded = std::fs::read_to_string(path)
+        .map_err(LoadError::Read)
+        .and_then(|contents| catalog.insert_pack(&contents).map_err(LoadError::Parse));
+
+    // The catalog lints every puzzle in the pack and leaves out the ones that fail
+    let loaded = match loaded {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            eprintln!("error: {}: {err}", path.display());
+            return (1, 1);
+        }
+    };
+
+    for (shortname, err) in &loaded.skipped {
+        match err.line() {
+            Some(line) => eprintln!("error: {}:{line} ({shortname}): {err}", path.display()),
+            None => eprintln!("error: {} ({shortname}): {err}", path.display()),
+        }
+    }
+
+    (loaded.added + loaded.skipped.len(), loaded.skipped.len())
+}
+
+fn rate(paths: &[PathBuf]) -> Result<(), String> {
+    let mut catalog = Catalog::default();
+
+    for root in paths {
+        for file in WalkDir::new(root)
+            .into_iter()
+            .filter_map(|d| d.ok())
+            .filter(|direntry| direntry.file_type().is_file())
+        {
+            let path = file.path();
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            // A single file is its own root, so it's named after the file
+            let name = if relative.as_os_str().is_empty() {
+                path.file_name().map(Path::new).unwrap_or(path)
+            } else {
+                relative
+            };
+
+            let contents = std::fs::read_to_string(path)
+                .map_err(|err| format!("{}: failed to read puzzle: {err}", path.display()))?;
+            match catalog.insert_file(&name.to_string_lossy(), contents) {
+                Some(Ok(loaded)) => {
+                    for (shortname, err) in loaded.skipped {
+                        let location = match err.line() {
+                            Some(line) => format!("{}:{line}", path.display()),
+                            None => path.display().to_string(),
+                        };
+                        eprintln!("warning: {location}: skipping {shortname}: {err}");
+                    }
+                }
+                Some(Err(err)) => {
+                    return Err(format!("{}: failed to parse puzzle: {err}", path.display()));
+                }
+                None => {}
+            }
+        }
+    }
+
+    catalog.rate_all();
+
+    let mut entries = catalog.entries().iter().collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.difficulty);
+    for entry in entries {
+        if let Some(difficulty) = entry.difficulty {
+            println!(
+                "{:>4}  {:<6}  {}",
+                difficulty.0,
+                difficulty.label(),
+                entry.shortname
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn solve(file: &Path) -> Result<(), String> {
+    let puzzle = load_puzzle(file)?;
+    let board = Board::from_definition(&puzzle);
+
+    let solution = Solver::from_board(&board)
+        .first_solution()
+        .ok_or_else(|| format!("{}: the puzzle has no solution", file.display()))?;
+
+    print!("{}", render::render(&board, &solution));
+    Ok(())
+}
+
+fn count_solutions(file: &Path, limit: Option<usize>) -> Result<(), String> {
+    let puzzle = load_puzzle(file)?;
+
+    let solver = Solver::new(&puzzle);
+    let result = match limit {
+        Some(limit) => solver.limit(limit).solve(),
+        None => solver.solve(),
+    };
+
+    if result.capped {
+        println!("at least {}", result.count());
+    } else {
+        println!("{}", result.count());
+    }
+    Ok(())
+}
+
+fn render_puzzle(file: &Path) -> Result<(), String> {
+    let puzzle = load_puzzle(file)?;
+    let board = Board::from_definition(&puzzle);
+
+    println!("{}", puzzle.name);
+    print!("{}", render::render(&board, &[]));
+
+    for (shape_idx, _) in puzzle
+        .shapes
+        .iter()
+        .enumerate()
+        .filter(|(_, shape)| shape.interactable)
+    {
+        println!();
+        print!("{}", render::render_piece(&board, shape_idx));
+    }
+
+    Ok(())
+}
+
+fn convert(file: &Path, to: Option<Format>) -> Result<(), String> {
+    let (puzzle, format) = load(file)
+        .ok_or_else(|| format!("{}: not a .yaml or .txt puzzle", file.display()))?
+        .map_err(|err| format!("{}: {err}", file.display()))?;
+
+    let to = to.unwrap_or(match format {
+        PuzzleFormat::Yaml => Format::Ascii,
+        PuzzleFormat::AsciiArt => Format::Yaml,
+    });
+
+    match to {
+        Format::Yaml => print!("{}", serde_yaml::to_string(&puzzle).unwrap()),
+        Format::Ascii => print!(
+            "{}",
+            render::to_ascii_art(&puzzle).ok_or_else(|| format!(
+                "{}: the puzzle is unsolvable or can't be drawn as ASCII art",
+                file.display()
+            ))?
+        ),
+    }
+
+    Ok(())
+}
+
+fn generate(seed: Option<u64>, options: GeneratorOptions, to: Format) -> Result<(), String> {
+    if matches!(to, Format::Ascii) && options.grid != Grid::Square {
+        return Err("only square grids can be drawn as ASCII art".to_string());
+    }
+
+    let seed = seed.unwrap_or_else(|| {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        eprintln!("seed: {seed}");
+        seed
+    });
+
+    let puzzle = generator::generate(seed, &options).map_err(|err| match err {
+        GenerateError::EmptyBoard => "the board must be at least 1 cell wide and high".to_string(),
+        GenerateError::PieceSizes { .. } => {
+            "--min-piece-size can't be more than --max-piece-size".to_string()
+        }
+        GenerateError::NoPuzzle { .. } => format!("{err}, try another seed or more attempts"),
+    })?;
+
+    match to {
+        Format::Yaml => print!("{}", serde_yaml::to_string(&puzzle).unwrap()),
+        Format::Ascii => print!(
+            "{}",
+            render::to_ascii_art(&puzzle).ok_or_else(|| {
+                "the puzzle has too many pieces to draw as ASCII art, try bigger pieces".to_string()
+            })?
+        ),
+    }
+
+    Ok(())
+}
+
+/// Loads a puzzle file, returning `None` if the file isn't a puzzle
+fn load(path: &Path) -> Option<Result<(PuzzleDefinition, PuzzleFormat), LoadError>> {
+    let file_name = path.file_name()?.to_str()?;
+    let (shortname, format) = PuzzleFormat::from_file_name(file_name)?;
+
+    Some(
+        std::fs::read_to_string(path)
+            .map_err(LoadError::Read)
+            .and_then(|contents| format.parse(shortname, contents).map_err(LoadError::Parse))
+            .map(|puzzle| (puzzle, format)),
+    )
+}
+
+fn load_puzzle(file: &Path) -> Result<PuzzleDefinition, String> {
+    match load(file) {
+        Some(Ok((puzzle, _))) => Ok(puzzle),
+        Some(Err(err)) => Err(format!("{}: {err}", file.display())),
+        None => Err(format!("{}: not a .yaml or .txt puzzle", file.display())),
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::ops::{Add, Sub};
+
+use crate::grid::Grid;
+use crate::orientation::Orientation;
+use crate::puzzles::{Position, PuzzleDefinition, TileType};
+
+/// A cell on the board grid. Unlike [`Position`] this is signed, since pieces can be dropped
+/// anywhere, including above or to the left of the board.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cell(pub i32, pub i32);
+
+impl Add for Cell {
+    type Output = Self;
+    fn add(self, rhs: Cell) -> Self {
+        Cell(self.0 + rhs.0, self.1 + rhs.1)
+    }
+}
+
+impl Sub for Cell {
+    type Output = Self;
+    fn sub(self, rhs: Cell) -> Self {
+        Cell(self.0 - rhs.0, self.1 - rhs.1)
+    }
+}
+
+impl From<Position> for Cell {
+    fn from(pos: Position) -> Self {
+        Cell(pos.0 as i32, pos.1 as i32)
+    }
+}
+
+/// Where an interactable shape has been dropped: `offset` is the cell its `Position(0, 0)` tile
+/// lands on once the shape is turned to `orientation`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Placement {
+    pub shape_idx: usize,
+    pub offset: Cell,
+    pub orientation: Orientation,
+}
+
+/// A tile of a shape, by its index in [`ShapeDefinition::get_tiles`]
+///
+/// [`ShapeDefinition::get_tiles`]: crate::puzzles::ShapeDefinition::get_tiles
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TileRef {
+    pub shape_idx: usize,
+    pub tile_idx: usize,
+}
+
+/// Everything wrong with a set of placements
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Background tiles with no foreground tile on them
+    pub uncovered: Vec<TileRef>,
+    /// Foreground tiles which aren't on a background tile, including every foreground tile of
+    /// shapes that haven't been placed
+    pub off_board: Vec<TileRef>,
+    /// Foreground tiles sharing a cell with another foreground tile, unless the puzzle allows
+    /// overlaps
+    pub overlapping: Vec<TileRef>,
+}
+
+impl ValidationReport {
+    /// Returns true if the placements solve the puzzle
+    pub fn is_valid(&self) -> bool {
+        self.uncovered.is_empty() && self.off_board.is_empty() && self.overlapping.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct BoardShape {
+    pub(crate) tiles: Vec<(Position, TileType)>,
+    pub(crate) fixed_offset: Option<Cell>,
+    pub(crate) orientations: Vec<Orientation>,
+    /// The shape's label, if it's a single character
+    pub(crate) label: Option<char>,
+}
+
+impl BoardShape {
+    pub(crate) fn oriented_tiles(
+        &self,
+        grid: Grid,
+        orientation: Orientation,
+    ) -> Vec<(Position, TileType)> {
+        let positions = self.tiles.iter().map(|(pos, _)| *pos).collect::<Vec<_>>();
+
+        orientation
+            .apply(grid, &positions)
+            .into_iter()
+            .zip(self.tiles.iter().map(|(_, tile_type)| *tile_type))
+            .collect()
+    }
+}
+
+/// The engine-independent state of a puzzle: which tiles each shape has and where the
+/// non-interactable shapes sit.
+#[derive(Debug, Clone)]
+pub struct Board {
+    pub(crate) shapes: Vec<BoardShape>,
+    pub(crate) grid: Grid,
+    allow_overlap: bool,
+}
+
+impl Board {
+    pub fn from_definition(definition: &PuzzleDefinition) -> Self {
+        let shapes = definition
+            .shapes
+            .iter()
+            .map(|shape_def| {
+                let base_type = TileType::from_interactable(shape_def.interactable);
+
+                BoardShape {
+                    tiles: shape_def
+                        .get_tiles()
+                        .iter()
+                        .map(|tile| (tile.pos, tile.tile_type.unwrap_or(base_type)))
+                        .collect(),
+                    fixed_offset: (!shape_def.interactable)
+                        .then(|| Cell::from(shape_def.pos.unwrap_or(Position(0, 0)))),
+                    orientations: Orientation::all(
+                        definition.grid,
+                        shape_def.can_rotate(definition),
+                        shape_def.can_flip(definition),
+                    ),
+                    label: shape_def.label.as_deref().and_then(|label| {
+                        let mut chars = label.chars();
+                        match (chars.next(), chars.next()) {
+                            (Some(chr), None) => Some(chr),
+                            _ => None,
+                        }
+                    }),
+                }
+            })
+            .collect();
+
+        Board {
+            shapes,
+            grid: definition.grid,
+            allow_overlap: definition.allow_overlap,
+        }
+    }
+
+    /// Checks every background tile is covered by exactly one foreground tile and every
+    /// foreground tile lies on a background tile. If the puzzle allows overlaps, background
+    /// tiles may be covered more than once.
+    ///
+    /// Interactable shapes without a placement count as being off the board, as do shapes
+    /// placed where the grid has no cells of the right shape for their tiles.
+    pub fn validate(&self, placements: &[Placement]) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let mut foreground: HashMap<Cell, Vec<TileRef>> = HashMap::new();
+        let mut background: HashMap<Cell, Vec<TileRef>> = HashMap::new();
+
+        for (shape_idx, shape) in self.shapes.iter().enumerate() {
+            let placement = match shape.fixed_offset {
+                Some(offset) => Some((offset, Orientation::default())),
+                None => placements
+                    .iter()
+                    .find(|placement| placement.shape_idx == shape_idx)
+                    .filter(|placement| self.grid.is_translation(placement.offset))
+                    .map(|placement| (placement.offset, placement.orientation)),
+            };
+
+            let (offset, orientation) = match placement {
+                Some(placement) => placement,
+                None => {
+                    report.off_board.extend(
+                        shape
+                            .tiles
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, (_, tile_type))| *tile_type == TileType::Foreground)
+                            .map(|(tile_idx, _)| TileRef {
+                                shape_idx,
+                                tile_idx,
+                            }),
+                    );
+                    continue;
+                }
+            };
+
+            for (tile_idx, (pos, tile_type)) in shape
+                .oriented_tiles(self.grid, orientation)
+                .into_iter()
+                .enumerate()
+            {
+                let cells = match tile_type {
+                    TileType::Foreground => &mut foreground,
+                    TileType::Background => &mut background,
+                };
+
+                cells
+                    .entry(offset + Cell::from(pos))
+                    .or_default()
+                    .push(TileRef {
+                        shape_idx,
+                        tile_idx,
+                    });
+            }
+        }
+
+        for (cell, tiles) in &background {
+            if !foreground.contains_key(cell) {
+                report.uncovered.extend(tiles);
+            }
+        }
+
+        for (cell, tiles) in &foreground {
+            if !background.contains_key(cell) {
+                report.off_board.extend(tiles);
+            }
+
+            if tiles.len() > 1 && !self.allow_overlap {
+                report.overlapping.extend(tiles);
+            }
+        }
+
+        report.uncovered.sort();
+        report.off_board.sort();
+        report.overlapping.sort();
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::bundled;
+
+    fn placed(shape_idx: usize, x: i32, y: i32) -> Placement {
+        Placement {
+            shape_idx,
+            offset: Cell(x, y),
+            orientation: Orientation::default(),
+        }
+    }
+
+    fn tile(shape_idx: usize, tile_idx: usize) -> TileRef {
+        TileRef {
+            shape_idx,
+            tile_idx,
+        }
+    }
+
+    #[test]
+    fn accepts_a_solution() {
+        let board = Board::from_definition(&bundled("0.intro/intro1.yaml"));
+        let placements = [
+            placed(1, 0, 0),
+            placed(2, 0, 1),
+            placed(3, 1, 0),
+            placed(4, 1, 1),
+        ];
+
+        assert!(board.validate(&placements).is_valid());
+    }
+
+    #[test]
+    fn accepts_a_turned_piece() {
+        let board = Board::from_definition(&bundled("0.intro/intro2.yaml"));
+        // The L tromino turned once clockwise fills every cell but the bottom right
+        let placements = [
+            placed(1, 1, 1),
+            Placement {
+                orientation: Orientation {
+                    turns: 1,
+                    flipped: false,
+                },
+                ..placed(2, 0, 0)
+            },
+        ];
+
+        assert!(board.validate(&placements).is_valid());
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let board = Board::from_definition(&bundled("0.intro/intro1.yaml"));
+        let placements = [
+            placed(1, 0, 0),
+            placed(2, 0, 0),
+            placed(3, 1, 0),
+            placed(4, 3, 0),
+        ];
+
+        assert_eq!(
+            board.validate(&placements),
+            ValidationReport {
+                uncovered: vec![tile(0, 1), tile(0, 3)],
+                off_board: vec![tile(4, 0)],
+                overlapping: vec![tile(1, 0), tile(2, 0)],
+            }
+        );
+    }
+
+    #[test]
+    fn unplaced_pieces_are_off_the_board() {
+        let board = Board::from_definition(&bundled("0.intro/intro0.yaml"));
+        let report = board.validate(&[]);
+
+        assert_eq!(report.uncovered, vec![tile(0, 0), tile(0, 1)]);
+        assert_eq!(report.off_board, vec![tile(1, 0), tile(1, 1)]);
+        assert!(report.overlapping.is_empty());
+    }
+}
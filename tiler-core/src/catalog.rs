@@ -0,0 +1,253 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::chapters;
+use crate::difficulty::{self, Difficulty};
+use crate::lint::{lint, LintError};
+use crate::puzzles::{PuzzleDefinition, PuzzleFormat};
+use crate::span::Span;
+
+/// File names ending in this hold a whole [`PackFile`] rather than a single puzzle
+pub const PACK_SUFFIX: &str = ".pack.yaml";
+
+/// Every puzzle the game can offer, in level select order
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    entries: Vec<CatalogEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CatalogEntry {
+    pub shortname: String,
+    pub definition: PuzzleDefinition,
+    /// `None` until the puzzle has been rated
+    pub difficulty: Option<Difficulty>,
+}
+
+/// What happened to the puzzles in a file added to a [`Catalog`]
+#[derive(Debug, Default)]
+pub struct Loaded {
+    pub added: usize,
+    /// Puzzles left out because they failed [`lint`], by short name
+    pub skipped: Vec<(String, LintError)>,
+}
+
+/// A pack of puzzles in a single file, for packs which can't be read as a directory, like ones
+/// fetched from a URL
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackFile {
+    pub puzzles: Vec<PackedPuzzle>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackedPuzzle {
+    pub shortname: String,
+    pub puzzle: PackedDefinition,
+}
+
+/// A puzzle written out in YAML, or as ASCII art in a string
+#[derive(Debug, Serialize, Deserialize)]
+pub enum PackedDefinition {
+    AsciiArt(String),
+    Yaml(PuzzleDefinition),
+}
+
+impl Catalog {
+    pub fn entries(&self) -> &[CatalogEntry] {
+        &self.entries
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&CatalogEntry> {
+        self.entries.get(idx)
+    }
+
+    pub fn index_of(&self, shortname: &str) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|entry| entry.shortname == shortname)
+    }
+
+    /// Sorts the puzzles from `start` onwards with [`puzzle_order`]
+    pub fn sort_from(&mut self, start: usize) {
+        self.entries[start..].sort_by(|entry1, entry2| {
+            puzzle_order(
+                (&entry1.shortname, &entry1.definition),
+                (&entry2.shortname, &entry2.definition),
+            )
+        });
+    }
+
+    /// Adds a puzzle to the end of the catalog. A puzzle with the same short name is replaced
+    /// where it is instead, so packs can update puzzles without moving them.
+    pub fn insert(&mut self, shortname: String, definition: PuzzleDefinition) {
+        self.insert_rated(shortname, definition, None);
+    }
+
+    /// Adds a puzzle like [`Catalog::insert`], along with its difficulty if it's already been
+    /// rated
+    pub fn insert_rated(
+        &mut self,
+        shortname: String,
+        definition: PuzzleDefinition,
+        difficulty: Option<Difficulty>,
+    ) {
+        match self.index_of(&shortname) {
+            Some(idx) => {
+                self.entries[idx].definition = definition;
+                self.entries[idx].difficulty = difficulty;
+            }
+            None => self.entries.push(CatalogEntry {
+                shortname,
+                definition,
+                difficulty,
+            }),
+        }
+    }
+
+    /// Rates every puzzle which hasn't been rated yet
+    pub fn rate_all(&mut self) {
+        for entry in &mut self.entries {
+            if entry.difficulty.is_none() {
+                entry.difficulty = Some(difficulty::rate(&entry.definition));
+            }
+        }
+    }
+
+    /// Adds the puzzles in a file, where `path` is the file's path within its pack. Puzzles which
+    /// fail [`lint`] are skipped. Returns `None` if the file isn't a puzzle or pack.
+    pub fn insert_file(
+        &mut self,
+        path: &str,
+        contents: String,
+    ) -> Option<Result<Loaded, serde_yaml::Error>> {
+        if path.ends_with(PACK_SUFFIX) {
+            return Some(self.insert_pack(&contents));
+        }
+
+        let (shortname, format) = PuzzleFormat::from_file_name(path)?;
+        Some(format.parse(shortname, contents).map(|definition| {
+            let mut loaded = Loaded::default();
+            self.insert_checked(shortname.to_string(), definition, &mut loaded);
+            loaded
+        }))
+    }
+
+    /// Adds every puzzle in a [`PackFile`] which passes [`lint`]
+    pub fn insert_pack(&mut self, contents: &str) -> Result<Loaded, serde_yaml::Error> {
+        let pack = serde_yaml::from_str::<PackFile>(contents)?;
+        let mut spans = Span::pack_from_yaml(contents).into_iter();
+        let mut loaded = Loaded::default();
+
+        for PackedPuzzle { shortname, puzzle } in pack.puzzles {
+            let definition = match puzzle {
+                PackedDefinition::AsciiArt(art) => {
+                    PuzzleDefinition::from_ascii_art(shortname.clone(), art)
+                }
+                PackedDefinition::Yaml(definition) => definition,
+            };
+            let definition = PuzzleDefinition {
+                span: spans.next().unwrap_or_default(),
+                ..definition
+            };
+
+            self.insert_checked(shortname, definition, &mut loaded);
+        }
+
+        Ok(loaded)
+    }
+
+    fn insert_checked(
+        &mut self,
+        shortname: String,
+        definition: PuzzleDefinition,
+        loaded: &mut Loaded,
+    ) {
+        match lint(&definition) {
+            Ok(()) => {
+                self.insert(shortname, definition);
+                loaded.added += 1;
+            }
+            Err(err) => loaded.skipped.push((shortname, err)),
+        }
+    }
+}
+
+/// The stable order of puzzles, which catalog indices follow: by directory, in chapter order,
+/// then by each puzzle's `order`, then by short name. This never depends on the file system, so
+/// indices are the same on every machine.
+pub fn puzzle_order(
+    (shortname1, definition1): (&str, &PuzzleDefinition),
+    (shortname2, definition2): (&str, &PuzzleDefinition),
+) -> Ordering {
+    type Key<'a> = (bool, Option<u32>, &'a str, bool, Option<i32>);
+
+    fn key<'a>(shortname: &'a str, definition: &PuzzleDefinition) -> Key<'a> {
+        let dir = shortname.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+        let (chapter, _) = chapters::split_dir(dir);
+
+        (
+            chapter.is_none(),
+            chapter,
+            dir,
+            definition.order.is_none(),
+            definition.order,
+        )
+    }
+
+    key(shortname1, definition1)
+        .cmp(&key(shortname2, definition2))
+        .then_with(|| shortname1.cmp(shortname2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_skip_puzzles_that_fail_lint() {
+        let pack = r#"
+puzzles:
+  - shortname: good
+    puzzle: !AsciiArt "AB\nAB\n"
+  - shortname: empty
+    puzzle: !AsciiArt ""
+  - shortname: holey
+    puzzle: !Yaml
+      name: Holey
+      shapes:
+        - interactable: false
+          tiles: !Rect [2, 1]
+        - tiles: !Rect [1, 1]
+"#;
+
+        let mut catalog = Catalog::default();
+        let loaded = catalog.insert_pack(pack).unwrap();
+
+        assert_eq!(loaded.added, 1);
+        assert!(matches!(
+            loaded.skipped.as_slice(),
+            [
+                (empty, LintError::NoPieces { line: Some(6) }),
+                (holey, LintError::TileCountMismatch { line: Some(9), .. }),
+            ] if empty == "empty" && holey == "holey"
+        ));
+        assert_eq!(catalog.index_of("good"), Some(0));
+        assert_eq!(catalog.entries().len(), 1);
+    }
+
+    #[test]
+    fn chapters_are_ordered_by_number() {
+        let definition = PuzzleDefinition::from_ascii_art("A".to_string(), "A\n".to_string());
+        let mut shortnames = ["extra/a", "10.late/a", "2.early/b", "2.early/a", "a"];
+
+        shortnames.sort_by(|shortname1, shortname2| {
+            puzzle_order((shortname1, &definition), (shortname2, &definition))
+        });
+
+        assert_eq!(
+            shortnames,
+            ["2.early/a", "2.early/b", "10.late/a", "a", "extra/a"]
+        );
+    }
+}
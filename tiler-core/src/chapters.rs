@@ -0,0 +1,109 @@
+use crate::catalog::Catalog;
+use crate::save::SaveData;
+
+/// The puzzles in one directory. Directories named like `0.intro` are ordered by their number,
+/// which is left out of the title.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    /// The directory the puzzles are in, relative to their pack. Empty for puzzles that aren't in
+    /// a directory.
+    pub dir: String,
+    pub title: String,
+    /// `None` if the directory isn't numbered, in which case the chapter comes after the
+    /// numbered ones
+    pub order: Option<u32>,
+    /// The catalog index of each puzzle, in catalog order
+    pub puzzles: Vec<usize>,
+}
+
+impl Chapter {
+    fn from_dir(dir: &str) -> Self {
+        let (order, name) = split_dir(dir);
+
+        Chapter {
+            dir: dir.to_string(),
+            title: title_case(name),
+            order,
+            puzzles: vec![],
+        }
+    }
+
+    /// Returns true once every puzzle in the chapter has been solved
+    pub fn is_complete(&self, catalog: &Catalog, save: &SaveData) -> bool {
+        self.puzzles
+            .iter()
+            .all(|idx| matches!(catalog.get(*idx), Some(entry) if save.is_solved(&entry.shortname)))
+    }
+}
+
+/// Splits a directory named like `0.intro` into its number and name. Directories which aren't
+/// numbered have no number, and their whole name is kept.
+pub(crate) fn split_dir(dir: &str) -> (Option<u32>, &str) {
+    match dir.split_once('.') {
+        Some((order, name)) => match order.parse::<u32>() {
+            Ok(order) => (Some(order), name),
+            Err(_) => (None, dir),
+        },
+        None => (None, dir),
+    }
+}
+
+/// Groups the catalog's puzzles into chapters, in play order
+pub fn chapters(catalog: &Catalog) -> Vec<Chapter> {
+    let mut chapters: Vec<Chapter> = vec![];
+
+    for (idx, entry) in catalog.entries().iter().enumerate() {
+        let dir = entry
+            .shortname
+            .rsplit_once('/')
+            .map(|(dir, _)| dir)
+            .unwrap_or("");
+
+        match chapters.iter_mut().find(|chapter| chapter.dir == dir) {
+            Some(chapter) => chapter.puzzles.push(idx),
+            None => {
+                let mut chapter = Chapter::from_dir(dir);
+                chapter.puzzles.push(idx);
+                chapters.push(chapter);
+            }
+        }
+    }
+
+    chapters.sort_by(|c1, c2| {
+        (c1.order.is_none(), c1.order, &c1.dir).cmp(&(c2.order.is_none(), c2.order, &c2.dir))
+    });
+    chapters
+}
+
+/// Returns the puzzle after `puzzle_idx` in play order, if there is one and its chapter is among
+/// the first `unlocked`
+pub fn next_puzzle(chapters: &[Chapter], unlocked: usize, puzzle_idx: usize) -> Option<usize> {
+    let mut puzzles = chapters
+        .iter()
+        .take(unlocked)
+        .flat_map(|chapter| chapter.puzzles.iter().copied());
+
+    puzzles.find(|idx| *idx == puzzle_idx)?;
+    puzzles.next()
+}
+
+/// Returns how many chapters can be played: the first one, and each one after a completed chapter
+pub fn unlocked_count(chapters: &[Chapter], catalog: &Catalog, save: &SaveData) -> usize {
+    let completed = chapters
+        .iter()
+        .take_while(|chapter| chapter.is_complete(catalog, save))
+        .count();
+
+    usize::min(completed + 1, chapters.len())
+}
+
+/// Turns a directory name like `daily_puzzles` into `Daily puzzles`
+fn title_case(name: &str) -> String {
+    let name = name.replace(['_', '-'], " ");
+
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => "Puzzles".to_string(),
+    }
+}
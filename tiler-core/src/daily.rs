@@ -0,0 +1,202 @@
+use std::fmt;
+
+use crate::generator::{self, GenerateError, GeneratorOptions};
+use crate::puzzles::PuzzleDefinition;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+const BOARD_WIDTH: usize = 6;
+const BOARD_HEIGHT: usize = 6;
+/// How many seeds, starting from the date's own, to cut the board up with before giving up
+const MAX_SEEDS: u64 = 10;
+
+/// A day of the Gregorian calendar, in UTC
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// Returns the UTC date at a Unix timestamp
+    pub fn from_unix_time(seconds: i64) -> Self {
+        Self::from_days_since_epoch(seconds.div_euclid(SECONDS_PER_DAY))
+    }
+
+    /// Returns the date `days` days after 1970-01-01, using Howard Hinnant's `civil_from_days`
+    pub fn from_days_since_epoch(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        Date {
+            year: year as i32,
+            month: month as u32,
+            day: day as u32,
+        }
+    }
+
+    /// Parses a date written as `YYYY-MM-DD`. Doesn't check the day exists in that month.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut parts = s.splitn(3, '-');
+        let date = Date {
+            year: parts.next()?.parse().ok()?,
+            month: parts.next()?.parse().ok()?,
+            day: parts.next()?.parse().ok()?,
+        };
+
+        ((1..=12).contains(&date.month) && (1..=31).contains(&date.day)).then_some(date)
+    }
+
+    /// The seed of the date's puzzle, which is just the date written as `YYYYMMDD`
+    pub fn seed(self) -> u64 {
+        self.year as u64 * 10_000 + self.month as u64 * 100 + self.day as u64
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Builds the puzzle for a date. Everyone gets the same puzzle on the same day. Fails if none
+/// of the [`MAX_SEEDS`] seeds from the date's own cut the board into a puzzle with one solution.
+pub fn puzzle(date: Date) -> Result<PuzzleDefinition, GenerateError> {
+    let options = GeneratorOptions {
+        unique_solution: true,
+        ..GeneratorOptions::rect(BOARD_WIDTH, BOARD_HEIGHT)
+    };
+
+    // Cutting the board up only fails by chance, so the next seed along will do instead
+    let mut puzzle = (date.seed()..date.seed() + MAX_SEEDS)
+        .find_map(|seed| generator::generate(seed, &options).ok())
+        .ok_or(GenerateError::NoPuzzle {
+            attempts: MAX_SEEDS as usize * options.max_attempts,
+        })?;
+    puzzle.name = format!("Daily {date}");
+
+    Ok(puzzle)
+}
+
+/// A summary of how the day's puzzle went, to be shared without giving the solution away
+pub fn share_result(date: Date, elapsed: f64, moves: usize, hints: usize) -> String {
+    let hints = match hints {
+        0 => "no hints".to_string(),
+        1 => "1 hint".to_string(),
+        hints => format!("{hints} hints"),
+    };
+
+    format!(
+        "Tiler Daily {date}\nSolved in {} with {moves} move(s) and {hints}",
+        format_time(elapsed)
+    )
+}
+
+/// Formats seconds as `M:SS`, or `H:MM:SS` once it's been an hour
+pub fn format_time(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render;
+    use crate::solver::Solver;
+
+    fn date(year: i32, month: u32, day: u32) -> Date {
+        Date { year, month, day }
+    }
+
+    #[test]
+    fn dates_from_unix_time() {
+        for (seconds, expected) in [
+            (0, date(1970, 1, 1)),
+            (-1, date(1969, 12, 31)),
+            (1_704_067_199, date(2023, 12, 31)),
+            (1_704_067_200, date(2024, 1, 1)),
+            (1_709_208_000, date(2024, 2, 29)),
+            (1_709_251_200, date(2024, 3, 1)),
+            (4_107_542_399, date(2100, 2, 28)),
+            (4_107_542_400, date(2100, 3, 1)),
+        ] {
+            assert_eq!(Date::from_unix_time(seconds), expected, "{}", seconds);
+        }
+    }
+
+    #[test]
+    fn seeds_are_the_date() {
+        assert_eq!(date(2023, 12, 31).seed(), 20_231_231);
+        assert_eq!(date(2024, 1, 1).seed(), 20_240_101);
+        assert_eq!(date(2024, 2, 29).seed(), 20_240_229);
+        assert_eq!(Date::parse("2024-03-01").unwrap().seed(), 20_240_301);
+    }
+
+    #[test]
+    fn parses_dates() {
+        assert_eq!(Date::parse("2024-02-29"), Some(date(2024, 2, 29)));
+        assert_eq!(date(2024, 2, 9).to_string(), "2024-02-09");
+        assert_eq!(Date::parse("2024-13-01"), None);
+        assert_eq!(Date::parse("2024-01-00"), None);
+        assert_eq!(Date::parse("2024-01"), None);
+    }
+
+    #[test]
+    fn puzzles_are_fixed_by_date() {
+        for (date, art) in [
+            (
+                date(2023, 12, 31),
+                "BBBBDD\nBGGGCD\nFFGCCD\nFFGCCH\nFEEEHH\nEEAAAA\n",
+            ),
+            (
+                date(2024, 1, 1),
+                "EEGIID\nEGGGID\nAAGBID\nAABBFD\nHHCCFF\nHCCCFF\n",
+            ),
+            (
+                date(2024, 2, 29),
+                "CDDDDE\nCCCCEE\nGGHHEE\nGBHAAF\nGBBAAF\nGBBAFF\n",
+            ),
+        ] {
+            let puzzle = puzzle(date).unwrap();
+
+            assert_eq!(puzzle.name, format!("Daily {date}"));
+            assert_eq!(
+                render::to_ascii_art(&puzzle).as_deref(),
+                Some(art),
+                "{}",
+                date
+            );
+            assert_eq!(Solver::new(&puzzle).solve().count(), 1, "{}", date);
+        }
+    }
+
+    #[test]
+    fn formats_results() {
+        assert_eq!(format_time(75.9), "1:15");
+        assert_eq!(format_time(3725.0), "1:02:05");
+        assert_eq!(
+            share_result(date(2024, 1, 1), 75.0, 12, 1),
+            "Tiler Daily 2024-01-01\nSolved in 1:15 with 12 move(s) and 1 hint"
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::board::{Board, Cell, Placement};
+use crate::grid::Grid;
+use crate::lint::{lint, LintError};
+use crate::orientation::Orientation;
+use crate::puzzles::{
+    tiles_from_raw_positions, Position, PuzzleDefinition, ShapeDefinition, TileDefinition, TileType,
+};
+use crate::render;
+use crate::span::Span;
+
+/// What a cell of a design has been painted as
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Paint {
+    /// A board cell for a piece to cover
+    Board,
+    /// A board cell that's already filled in and can't be covered
+    Fixed,
+    /// A board cell covered by the piece with this id in the intended solution
+    Piece(usize),
+}
+
+/// A puzzle being drawn cell by cell, with every piece painted where it goes in the solution
+#[derive(Debug, Clone, Default)]
+pub struct Design {
+    pub name: String,
+    pub allow_rotation: bool,
+    pub allow_flip: bool,
+    cells: HashMap<Position, Paint>,
+}
+
+impl Design {
+    pub fn new(name: String) -> Self {
+        Design {
+            name,
+            ..Default::default()
+        }
+    }
+
+    pub fn get(&self, pos: Position) -> Option<Paint> {
+        self.cells.get(&pos).copied()
+    }
+
+    /// Returns every painted cell in reading order
+    pub fn cells(&self) -> Vec<(Position, Paint)> {
+        let mut cells = self
+            .cells
+            .iter()
+            .map(|(pos, paint)| (*pos, *paint))
+            .collect::<Vec<_>>();
+        cells.sort_by_key(|(pos, _)| (pos.1, pos.0));
+        cells
+    }
+
+    pub fn paint(&mut self, pos: Position, paint: Paint) {
+        self.cells.insert(pos, paint);
+    }
+
+    pub fn erase(&mut self, pos: Position) {
+        self.cells.remove(&pos);
+    }
+
+    /// Returns an id which no piece uses yet
+    pub fn new_piece_id(&self) -> usize {
+        self.piece_ids().last().map_or(0, |id| id + 1)
+    }
+
+    fn piece_ids(&self) -> BTreeSet<usize> {
+        self.cells
+            .values()
+            .filter_map(|paint| match paint {
+                Paint::Piece(id) => Some(*id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn piece_cells(&self, id: usize) -> Vec<Position> {
+        self.cells()
+            .into_iter()
+            .filter(|(_, paint)| *paint == Paint::Piece(id))
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+
+    /// Returns the design as a puzzle: the board first, then the fixed cells if there are any,
+    /// then each piece in id order. Returns `None` if nothing has been painted yet.
+    pub fn to_definition(&self) -> Option<PuzzleDefinition> {
+        let top_left = self.cells.keys().copied().reduce(Position::min)?;
+
+        let board_tile = |pos: Position, tile_type| TileDefinition {
+            pos: pos - top_left,
+            tile_type,
+        };
+        let board = self
+            .cells()
+            .into_iter()
+            .map(|(pos, _)| board_tile(pos, None))
+            .collect::<Vec<TileDefinition>>();
+        // Fixed cells are a shape of their own which is stuck on top of the board
+        let fixed = self
+            .cells()
+            .into_iter()
+            .filter(|(_, paint)| *paint == Paint::Fixed)
+            .map(|(pos, _)| board_tile(pos, Some(TileType::Foreground)))
+            .collect::<Vec<TileDefinition>>();
+
+        let pieces = self.piece_ids().into_iter().map(|id| {
+            ShapeDefinition::from_tiles(
+                tiles_from_raw_positions(Grid::Square, &self.piece_cells(id)),
+                true,
+            )
+        });
+
+        Some(PuzzleDefinition {
+            name: self.name.clone(),
+            shapes: [ShapeDefinition::from_tiles(board, false)]
+                .into_iter()
+                .chain((!fixed.is_empty()).then(|| ShapeDefinition::from_tiles(fixed, false)))
+                .chain(pieces)
+                .collect(),
+            grid: Grid::Square,
+            order: None,
+            unique_solution: false,
+            allow_rotation: self.allow_rotation,
+            allow_flip: self.allow_flip,
+            allow_overlap: false,
+            palette: vec![],
+            span: Span::default(),
+        })
+    }
+
+    /// Draws the design in the format read by [`PuzzleDefinition::from_ascii_art`]. Returns
+    /// `None` if the design is empty or can't be described in that format, because some cells
+    /// aren't covered by a piece or there are too many pieces to label.
+    pub fn to_ascii_art(&self) -> Option<String> {
+        let definition = self.to_definition()?;
+
+        let only_pieces = self
+            .cells
+            .values()
+            .all(|paint| matches!(paint, Paint::Piece(_)));
+        if !only_pieces || definition.shapes.len() - 1 > render::MAX_PIECE_LABELS {
+            return None;
+        }
+
+        Some(render::render(
+            &Board::from_definition(&definition),
+            &self.placements(),
+        ))
+    }
+
+    /// Returns the placement of every piece where it's been painted
+    fn placements(&self) -> Vec<Placement> {
+        let top_left = match self.cells.keys().copied().reduce(Position::min) {
+            Some(top_left) => top_left,
+            None => return vec![],
+        };
+
+        self.piece_ids()
+            .into_iter()
+            .enumerate()
+            .filter_map(|(piece, id)| {
+                let piece_top_left = self.piece_cells(id).into_iter().reduce(Position::min)?;
+
+                Some(Placement {
+                    shape_idx: piece + 1,
+                    offset: Cell::from(piece_top_left - top_left),
+                    orientation: Orientation::default(),
+                })
+            })
+            .collect()
+    }
+
+    /// Checks the design would make a good puzzle: the pieces exactly cover the board and
+    /// there's only one way to fit them together
+    pub fn check(&self) -> Option<Result<(), LintError>> {
+        let mut definition = self.to_definition()?;
+        definition.unique_solution = true;
+
+        Some(lint(&definition))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paint_rows(design: &mut Design, rows: &[&str]) {
+        for (y, row) in rows.iter().enumerate() {
+            for (x, chr) in row.chars().enumerate() {
+                let paint = match chr {
+                    '.' => Paint::Board,
+                    '#' => Paint::Fixed,
+                    _ => Paint::Piece(chr as usize - 'a' as usize),
+                };
+                design.paint(Position(x, y), paint);
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_through_ascii_art() {
+        let mut design = Design::new("Design".to_string());
+        paint_rows(&mut design, &["aab", "acc"]);
+
+        let definition = design.to_definition().unwrap();
+        // The board, then a shape per piece
+        assert_eq!(definition.shapes.len(), 4);
+
+        // The pieces only fit together one way, so solving the puzzle read back draws the same
+        let art = design.to_ascii_art().unwrap();
+        assert_eq!(art, "AAB\nACC\n");
+
+        let read_back = PuzzleDefinition::from_ascii_art("Design".to_string(), art.clone());
+        assert_eq!(render::to_ascii_art(&read_back), Some(art));
+    }
+
+    #[test]
+    fn designs_with_uncovered_cells_have_no_ascii_art() {
+        let mut design = Design::new("Design".to_string());
+        paint_rows(&mut design, &["aa.", "#bb"]);
+
+        assert_eq!(design.to_definition().unwrap().shapes.len(), 4);
+        assert_eq!(design.to_ascii_art(), None);
+        assert_eq!(Design::new("Empty".to_string()).to_ascii_art(), None);
+    }
+}
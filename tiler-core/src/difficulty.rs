@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::Board;
+use crate::orientation::Orientation;
+use crate::puzzles::{PuzzleDefinition, TileType};
+use crate::solver::{SearchStats, Solver};
+
+/// Solutions counted when rating a puzzle. Past this many, more make no real difference.
+const SOLUTION_LIMIT: usize = 20;
+
+/// How hard a puzzle is, measured by solving it. Higher is harder, and the score has no upper
+/// bound.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Difficulty(pub u32);
+
+impl Difficulty {
+    pub fn label(self) -> &'static str {
+        match self.0 {
+            0..=19 => "Easy",
+            20..=39 => "Medium",
+            40..=59 => "Hard",
+            _ => "Expert",
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.label(), self.0)
+    }
+}
+
+/// What a difficulty is worked out from
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DifficultyStats {
+    /// The search for every solution, up to a limit
+    pub search: SearchStats,
+    pub pieces: usize,
+    /// Pieces which look the same after being turned or flipped, so they're easier to fit
+    /// whether or not the puzzle lets them turn
+    pub symmetric_pieces: usize,
+    pub solutions: usize,
+}
+
+impl DifficultyStats {
+    pub fn new(definition: &PuzzleDefinition) -> Self {
+        let board = Board::from_definition(definition);
+        let result = Solver::from_board(&board).limit(SOLUTION_LIMIT).solve();
+
+        let pieces = board
+            .shapes
+            .iter()
+            .filter(|shape| shape.fixed_offset.is_none())
+            .collect::<Vec<_>>();
+        let orientations = Orientation::all(board.grid, true, true);
+        let symmetric_pieces = pieces
+            .iter()
+            .filter(|shape| {
+                let looks = orientations
+                    .iter()
+                    .map(|orientation| {
+                        let mut tiles = shape
+                            .oriented_tiles(board.grid, *orientation)
+                            .into_iter()
+                            .filter(|(_, tile_type)| *tile_type == TileType::Foreground)
+                            .map(|(pos, _)| (pos.1, pos.0))
+                            .collect::<Vec<(usize, usize)>>();
+                        tiles.sort();
+                        tiles
+                    })
+                    .collect::<HashSet<_>>();
+
+                looks.len() < orientations.len()
+            })
+            .count();
+
+        DifficultyStats {
+            search: result.stats,
+            pieces: pieces.len(),
+            symmetric_pieces,
+            solutions: result.count(),
+        }
+    }
+
+    /// Combines the stats into a score. A bigger search and more pieces make a puzzle harder,
+    /// while symmetric pieces and extra solutions give the player more ways to get it right.
+    pub fn difficulty(&self) -> Difficulty {
+        let asymmetric_pieces = self.pieces - self.symmetric_pieces;
+
+        let score = 4.0 * (self.search.nodes as f64 + 1.0).log2()
+            + 6.0 * self.search.branching_factor().max(1.0).log2()
+            + 2.0 * asymmetric_pieces as f64
+            + self.symmetric_pieces as f64
+            - 5.0 * (self.solutions.max(1) as f64).log2();
+
+        Difficulty(score.max(0.0).round() as u32)
+    }
+}
+
+pub fn rate(definition: &PuzzleDefinition) -> Difficulty {
+    DifficultyStats::new(definition).difficulty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::bundled;
+
+    #[test]
+    fn bundled_puzzles_get_harder_by_chapter() {
+        let intros = [
+            "0.intro/intro0.yaml",
+            "0.intro/intro1.yaml",
+            "0.intro/intro2.yaml",
+        ]
+        .map(|path| rate(&bundled(path)));
+        let grids =
+            ["1.hexagons/hex1.yaml", "2.triangles/triangle1.yaml"].map(|path| rate(&bundled(path)));
+        let complex = ["999.complex/complex1.yaml", "999.complex/complex2.txt"]
+            .map(|path| rate(&bundled(path)));
+
+        assert!(
+            intros.iter().max() < grids.iter().min(),
+            "{intros:?} {grids:?}"
+        );
+        assert!(
+            grids.iter().max() < complex.iter().min(),
+            "{grids:?} {complex:?}"
+        );
+        assert!(intros.iter().all(|difficulty| difficulty.label() == "Easy"));
+    }
+
+    #[test]
+    fn identical_pieces_are_one_choice() {
+        let stats = DifficultyStats::new(&bundled("0.intro/intro1.yaml"));
+
+        assert_eq!(stats.search.nodes, 4);
+        assert_eq!(stats.search.branching_factor(), 1.0);
+    }
+
+    #[test]
+    fn symmetry_is_measured_on_the_shape() {
+        // Intro 2 doesn't let its pieces turn, but the monomino and the L tromino both look the
+        // same after some turns
+        let stats = DifficultyStats::new(&bundled("0.intro/intro2.yaml"));
+
+        assert_eq!(stats.pieces, 2);
+        assert_eq!(stats.symmetric_pieces, 2);
+    }
+}
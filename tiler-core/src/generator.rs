@@ -0,0 +1,248 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::grid::Grid;
+use crate::orientation::Orientation;
+use crate::puzzles::{tiles_from_raw_positions, Position, PuzzleDefinition, ShapeDefinition};
+use crate::solver::Solver;
+use crate::span::Span;
+
+/// What kind of puzzle [`generate`] should make
+#[derive(Debug, Clone)]
+pub struct GeneratorOptions {
+    pub grid: Grid,
+    /// The cells of the board to cut into pieces
+    pub board: Vec<Position>,
+    pub min_piece_size: usize,
+    pub max_piece_size: usize,
+    pub allow_rotation: bool,
+    pub allow_flip: bool,
+    /// Keep generating until the puzzle has exactly one solution
+    pub unique_solution: bool,
+    /// How many boards to cut up before giving up
+    pub max_attempts: usize,
+}
+
+impl GeneratorOptions {
+    /// Options for a `width` by `height` board of squares cut into pieces of 3 to 5 tiles. The
+    /// pieces can't be turned, since turning every piece of a solution on a symmetric board like
+    /// this one gives another solution, so it could never be unique.
+    pub fn rect(width: usize, height: usize) -> Self {
+        Self::rect_on(Grid::Square, width, height)
+    }
+
+    /// Like [`GeneratorOptions::rect`], but on any grid. On hex grids the board is a rhombus,
+    /// and on triangle grids it's a parallelogram `width` triangles across.
+    pub fn rect_on(grid: Grid, width: usize, height: usize) -> Self {
+        GeneratorOptions {
+            grid,
+            board: itertools::iproduct!(0..height, 0..width)
+                .map(|(y, x)| Position(x, y))
+                .collect(),
+            min_piece_size: 3,
+            max_piece_size: 5,
+            allow_rotation: false,
+            allow_flip: false,
+            unique_solution: false,
+            max_attempts: 100,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GenerateError {
+    /// The board has no cells to cut up
+    EmptyBoard,
+    /// `min_piece_size` is bigger than `max_piece_size`
+    PieceSizes { min: usize, max: usize },
+    /// No attempt cut the board into pieces of the right sizes, or none of the cuts had a unique
+    /// solution when one was asked for
+    NoPuzzle { attempts: usize },
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GenerateError::EmptyBoard => write!(f, "the board has no cells"),
+            GenerateError::PieceSizes { min, max } => write!(
+                f,
+                "the smallest piece size {min} is bigger than the largest {max}"
+            ),
+            GenerateError::NoPuzzle { attempts } => {
+                write!(f, "no puzzle found in {attempts} attempt(s)")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GenerateError {}
+
+/// Makes a puzzle by cutting the board into random pieces. The same seed and options always
+/// make the same puzzle.
+pub fn generate(seed: u64, options: &GeneratorOptions) -> Result<PuzzleDefinition, GenerateError> {
+    if options.board.is_empty() {
+        return Err(GenerateError::EmptyBoard);
+    }
+    if options.min_piece_size > options.max_piece_size {
+        return Err(GenerateError::PieceSizes {
+            min: options.min_piece_size,
+            max: options.max_piece_size,
+        });
+    }
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    for _ in 0..options.max_attempts {
+        let pieces = match partition(&mut rng, options) {
+            Some(pieces) => pieces,
+            None => continue,
+        };
+
+        let definition = to_definition(&mut rng, seed, options, pieces);
+        if !options.unique_solution || Solver::new(&definition).limit(2).solve().count() == 1 {
+            return Ok(definition);
+        }
+    }
+
+    Err(GenerateError::NoPuzzle {
+        attempts: options.max_attempts,
+    })
+}
+
+/// Cuts the board into pieces. Each piece starts at the first cell left over, in reading
+/// order, and grows into random neighbouring cells until it reaches a random size. Pieces
+/// which get boxed in before reaching the minimum size are merged into a neighbour, and the
+/// attempt fails if that would make the neighbour too big.
+fn partition(rng: &mut ChaCha8Rng, options: &GeneratorOptions) -> Option<Vec<Vec<Position>>> {
+    let mut cells = options.board.clone();
+    cells.sort_by_key(|pos| (pos.1, pos.0));
+    let on_board = cells.iter().copied().collect::<HashSet<Position>>();
+
+    let mut pieces: Vec<Vec<Position>> = vec![];
+    let mut piece_of: HashMap<Position, usize> = HashMap::new();
+
+    for start in cells {
+        if piece_of.contains_key(&start) {
+            continue;
+        }
+
+        let size = rng.gen_range(options.min_piece_size..=options.max_piece_size);
+        let mut piece = vec![start];
+        piece_of.insert(start, pieces.len());
+
+        while piece.len() < size {
+            let mut frontier = vec![];
+            for neighbour in piece.iter().flat_map(|pos| options.grid.neighbours(*pos)) {
+                if on_board.contains(&neighbour)
+                    && !piece_of.contains_key(&neighbour)
+                    && !frontier.contains(&neighbour)
+                {
+                    frontier.push(neighbour);
+                }
+            }
+
+            match frontier.choose(rng) {
+                Some(next) => {
+                    piece_of.insert(*next, pieces.len());
+                    piece.push(*next);
+                }
+                None => break,
+            }
+        }
+
+        if piece.len() >= options.min_piece_size {
+            pieces.push(piece);
+            continue;
+        }
+
+        let merge_into = piece
+            .iter()
+            .flat_map(|pos| options.grid.neighbours(*pos))
+            .filter_map(|neighbour| piece_of.get(&neighbour).copied())
+            .find(|idx| {
+                *idx < pieces.len() && pieces[*idx].len() + piece.len() <= options.max_piece_size
+            })?;
+        for pos in piece {
+            piece_of.insert(pos, merge_into);
+            pieces[merge_into].push(pos);
+        }
+    }
+
+    Some(pieces)
+}
+
+/// Turns the cut up board into a puzzle, with the pieces shuffled and turned at random so
+/// their order and orientation don't give the solution away
+fn to_definition(
+    rng: &mut ChaCha8Rng,
+    seed: u64,
+    options: &GeneratorOptions,
+    mut pieces: Vec<Vec<Position>>,
+) -> PuzzleDefinition {
+    pieces.shuffle(rng);
+    let orientations = Orientation::all(options.grid, options.allow_rotation, options.allow_flip);
+
+    let pieces = pieces.into_iter().map(|piece| {
+        let orientation = *orientations.choose(rng).unwrap();
+        let piece = orientation.apply(options.grid, &piece);
+        ShapeDefinition::from_tiles(tiles_from_raw_positions(options.grid, &piece), true)
+    });
+
+    PuzzleDefinition {
+        name: format!("Random #{seed}"),
+        shapes: [ShapeDefinition::from_tiles(
+            tiles_from_raw_positions(options.grid, &options.board),
+            false,
+        )]
+        .into_iter()
+        .chain(pieces)
+        .collect(),
+        grid: options.grid,
+        order: None,
+        unique_solution: options.unique_solution,
+        allow_rotation: options.allow_rotation,
+        allow_flip: options.allow_flip,
+        allow_overlap: false,
+        palette: vec![],
+        span: Span::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lint::lint;
+
+    #[test]
+    fn generates_playable_puzzles() {
+        for grid in [Grid::Square, Grid::Hex, Grid::Triangle] {
+            let options = GeneratorOptions::rect_on(grid, 4, 3);
+            let puzzle = generate(1, &options).unwrap();
+
+            assert!(lint(&puzzle).is_ok(), "{:?}", grid);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_options() {
+        let empty = GeneratorOptions::rect(0, 3);
+        assert!(matches!(
+            generate(1, &empty),
+            Err(GenerateError::EmptyBoard)
+        ));
+
+        let inverted = GeneratorOptions {
+            min_piece_size: 5,
+            max_piece_size: 3,
+            ..GeneratorOptions::rect(4, 4)
+        };
+        assert!(matches!(
+            generate(1, &inverted),
+            Err(GenerateError::PieceSizes { min: 5, max: 3 })
+        ));
+    }
+}
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+
+use crate::board::Cell;
+use crate::orientation::Orientation;
+use crate::puzzles::Position;
+
+/// The shape of the cells a puzzle is made of.
+///
+/// Hex cells use axial coordinates with pointy-topped hexes: `Position(q, r)` is `q` cells right
+/// along its row, and each row down is shifted half a cell to the right of the one above.
+///
+/// Triangle cells are laid out in rows, with each cell sharing its sides with the cells either
+/// side of it. Cells where `x + y` is even point up and the others point down.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Grid {
+    #[default]
+    Square,
+    Hex,
+    Triangle,
+}
+
+impl Grid {
+    /// How many turns it takes to go all the way round: a turn is 90° on square grids and 60°
+    /// on hex and triangle grids
+    pub fn turns(self) -> u8 {
+        match self {
+            Grid::Square => 4,
+            Grid::Hex | Grid::Triangle => 6,
+        }
+    }
+
+    /// Returns true if moving a shape by `offset` keeps every tile on a cell of the same shape.
+    /// Triangles flip between pointing up and down when moved by an odd number of cells.
+    pub fn is_translation(self, offset: Cell) -> bool {
+        match self {
+            Grid::Square | Grid::Hex => true,
+            Grid::Triangle => (offset.0 + offset.1) % 2 == 0,
+        }
+    }
+
+    /// Shortens a move up and to the left by `by` so it's a translation, by moving one cell
+    /// less along one of the axes if it has to
+    pub(crate) fn translation_within(self, by: Position) -> Position {
+        match by {
+            Position(x, y) if self.is_translation(Cell::from(by)) || (x == 0 && y == 0) => by,
+            Position(0, y) => Position(0, y - 1),
+            Position(x, y) => Position(x - 1, y),
+        }
+    }
+
+    /// Returns the cells sharing a side with `pos`, leaving out any with negative coordinates
+    pub fn neighbours(self, Position(x, y): Position) -> Vec<Position> {
+        let (x, y) = (x as i64, y as i64);
+        let offsets: &[(i64, i64)] = match self {
+            Grid::Square => &[(-1, 0), (0, -1), (1, 0), (0, 1)],
+            Grid::Hex => &[(-1, 0), (0, -1), (1, -1), (1, 0), (0, 1), (-1, 1)],
+            Grid::Triangle if (x + y) % 2 == 0 => &[(-1, 0), (1, 0), (0, 1)],
+            Grid::Triangle => &[(-1, 0), (0, -1), (1, 0)],
+        };
+
+        offsets
+            .iter()
+            .filter(|(dx, dy)| x + dx >= 0 && y + dy >= 0)
+            .map(|(dx, dy)| Position((x + dx) as usize, (y + dy) as usize))
+            .collect()
+    }
+
+    /// Transforms tile positions, keeping the top-left corner of their bounding box where it
+    /// was. On triangle grids the shape may move one cell right so its tiles still point the
+    /// right way. The output is in the same order as `tiles`.
+    pub(crate) fn orient(self, orientation: Orientation, tiles: &[Position]) -> Vec<Position> {
+        let top_left = match tiles.iter().copied().reduce(Position::min) {
+            Some(top_left) => top_left,
+            None => return vec![],
+        };
+
+        let moved = tiles
+            .iter()
+            .map(|Position(x, y)| {
+                let (mut x, mut y) = (*x as i64, *y as i64);
+
+                if orientation.flipped {
+                    (x, y) = self.mirror(x, y);
+                }
+
+                for _ in 0..orientation.turns % self.turns() {
+                    (x, y) = self.turn(x, y);
+                }
+
+                (x, y)
+            })
+            .collect::<Vec<_>>();
+
+        let min_x = moved.iter().map(|(x, _)| *x).min().unwrap();
+        let min_y = moved.iter().map(|(_, y)| *y).min().unwrap();
+        let mut shift = (top_left.0 as i64 - min_x, top_left.1 as i64 - min_y);
+        if !self.is_translation(Cell(shift.0 as i32, shift.1 as i32)) {
+            shift.0 += 1;
+        }
+
+        moved
+            .into_iter()
+            .map(|(x, y)| Position((x + shift.0) as usize, (y + shift.1) as usize))
+            .collect()
+    }
+
+    /// Mirrors a cell horizontally, about a line through the origin on square and hex grids and
+    /// through the corner at the top of cell `(0, 0)` on triangle grids
+    fn mirror(self, x: i64, y: i64) -> (i64, i64) {
+        match self {
+            Grid::Square => (-x, y),
+            Grid::Hex => (-x - y, y),
+            Grid::Triangle => (-x, y),
+        }
+    }
+
+    /// Turns a cell one step clockwise, about the same point [`Grid::mirror`] uses
+    fn turn(self, x: i64, y: i64) -> (i64, i64) {
+        match self {
+            Grid::Square => (-y, x),
+            Grid::Hex => (-y, x + y),
+            Grid::Triangle => {
+                // Work on the centres of the cells, measured in half cell widths across and
+                // thirds of a row down from the corner they turn about
+                let (across, down) = (x, 3 * y + if (x + y) % 2 == 0 { 2 } else { 1 });
+                let (across, down) = ((across - down) / 2, (3 * across + down) / 2);
+
+                (across, down.div_euclid(3))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turning_all_the_way_round_changes_nothing() {
+        for grid in [Grid::Square, Grid::Hex, Grid::Triangle] {
+            for (x, y) in itertools::iproduct!(-3..=3, -3..=3) {
+                let turned = (0..grid.turns()).fold((x, y), |(x, y), _| grid.turn(x, y));
+                assert_eq!(turned, (x, y), "{:?}", grid);
+            }
+        }
+    }
+
+    #[test]
+    fn mirroring_twice_changes_nothing() {
+        for grid in [Grid::Square, Grid::Hex, Grid::Triangle] {
+            for (x, y) in itertools::iproduct!(-3..=3, -3..=3) {
+                let (mx, my) = grid.mirror(x, y);
+                assert_eq!(grid.mirror(mx, my), (x, y), "{:?}", grid);
+            }
+        }
+    }
+
+    #[test]
+    fn turning_keeps_triangles_pointing_the_right_way() {
+        for (x, y) in itertools::iproduct!(-3..=3, -3..=3) {
+            let (tx, ty) = Grid::Triangle.turn(x, y);
+            // A 60° turn swaps up and down triangles
+            assert_ne!((x + y).rem_euclid(2), (tx + ty).rem_euclid(2));
+        }
+    }
+}
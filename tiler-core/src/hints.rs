@@ -0,0 +1,197 @@
+use std::collections::HashSet;
+
+use crate::board::{Board, Cell, Placement, TileRef};
+use crate::puzzles::TileType;
+use crate::solver::Solver;
+
+/// What the solver suggests doing next
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Hint {
+    /// The placements already solve the puzzle
+    Solved,
+    /// Where a piece which isn't on the board yet goes. Pieces already on the board stay where
+    /// they are in the solution this comes from.
+    Place {
+        placement: Placement,
+        /// The board's background tiles the piece covers once it's placed
+        board_tiles: Vec<TileRef>,
+    },
+    /// This piece is on the board, but there's no solution with it there as well as the pieces
+    /// on the board before it
+    Misplaced(usize),
+    /// The puzzle can't be solved, wherever the pieces go
+    Unsolvable,
+}
+
+/// Works out a hint for the puzzle as it's laid out. Pieces count as on the board once every
+/// one of their tiles is over the board's background, and are kept where they are for as long
+/// as there's still a solution that way.
+pub fn hint(board: &Board, placements: &[Placement]) -> Hint {
+    if board.validate(placements).is_valid() {
+        return Hint::Solved;
+    }
+
+    let background = background_cells(board)
+        .into_iter()
+        .map(|(cell, _)| cell)
+        .collect::<HashSet<Cell>>();
+    let on_board = placements
+        .iter()
+        .filter(|placement| {
+            covered_cells(board, placement)
+                .iter()
+                .all(|cell| background.contains(cell))
+        })
+        .copied()
+        .collect::<Vec<Placement>>();
+
+    let mut solution = match solve_around(board, &[]) {
+        Some(solution) => solution,
+        None => return Hint::Unsolvable,
+    };
+
+    let mut kept = vec![];
+    for placement in on_board {
+        kept.push(placement);
+        match solve_around(board, &kept) {
+            Some(found) => solution = found,
+            None => return Hint::Misplaced(placement.shape_idx),
+        }
+    }
+
+    // Pieces left on the board aren't in the solution, so anything in it still needs placing
+    match solution.into_iter().next() {
+        Some(placement) => Hint::Place {
+            placement,
+            board_tiles: board_tiles(board, &placement),
+        },
+        None => Hint::Solved,
+    }
+}
+
+/// Solves the puzzle with the `kept` pieces fixed where they are, returning where the rest of
+/// the pieces go
+fn solve_around(board: &Board, kept: &[Placement]) -> Option<Vec<Placement>> {
+    let mut board = board.clone();
+
+    for placement in kept {
+        let shape = &mut board.shapes[placement.shape_idx];
+        shape.tiles = shape
+            .oriented_tiles(board.grid, placement.orientation)
+            .into_iter()
+            .filter(|(_, tile_type)| *tile_type == TileType::Foreground)
+            .collect();
+        shape.fixed_offset = Some(placement.offset);
+    }
+
+    Solver::from_board(&board).first_solution()
+}
+
+/// Returns the cells the foreground tiles of a placed piece are on
+fn covered_cells(board: &Board, placement: &Placement) -> Vec<Cell> {
+    board.shapes[placement.shape_idx]
+        .oriented_tiles(board.grid, placement.orientation)
+        .into_iter()
+        .filter(|(_, tile_type)| *tile_type == TileType::Foreground)
+        .map(|(pos, _)| placement.offset + Cell::from(pos))
+        .collect()
+}
+
+/// Returns every background tile of the shapes fixed in place, with the cell it's on
+fn background_cells(board: &Board) -> Vec<(Cell, TileRef)> {
+    board
+        .shapes
+        .iter()
+        .enumerate()
+        .filter_map(|(shape_idx, shape)| {
+            shape.fixed_offset.map(|offset| (shape_idx, shape, offset))
+        })
+        .flat_map(|(shape_idx, shape, offset)| {
+            shape
+                .tiles
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, tile_type))| *tile_type == TileType::Background)
+                .map(move |(tile_idx, (pos, _))| {
+                    (
+                        offset + Cell::from(*pos),
+                        TileRef {
+                            shape_idx,
+                            tile_idx,
+                        },
+                    )
+                })
+        })
+        .collect()
+}
+
+fn board_tiles(board: &Board, placement: &Placement) -> Vec<TileRef> {
+    let covered = covered_cells(board, placement)
+        .into_iter()
+        .collect::<HashSet<Cell>>();
+
+    background_cells(board)
+        .into_iter()
+        .filter(|(cell, _)| covered.contains(cell))
+        .map(|(_, tile)| tile)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orientation::Orientation;
+    use crate::testing::bundled;
+
+    fn monomino(shape_idx: usize, offset: Cell) -> Placement {
+        Placement {
+            shape_idx,
+            offset,
+            orientation: Orientation::default(),
+        }
+    }
+
+    #[test]
+    fn places_a_piece_on_an_empty_board() {
+        let board = Board::from_definition(&bundled("0.intro/intro1.yaml"));
+        let placements = (1..=4)
+            .map(|shape_idx| monomino(shape_idx, Cell(5, shape_idx as i32)))
+            .collect::<Vec<_>>();
+
+        match hint(&board, &placements) {
+            Hint::Place {
+                placement,
+                board_tiles,
+            } => {
+                assert!(board.shapes[placement.shape_idx].fixed_offset.is_none());
+                assert_eq!(board_tiles.len(), 1);
+            }
+            other => panic!("Expected a piece to place, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn finds_a_misplaced_piece() {
+        // Both monominoes are on the board, but on top of each other
+        let board = Board::from_definition(&bundled("0.intro/intro1.yaml"));
+        let placements = vec![
+            monomino(1, Cell(0, 0)),
+            monomino(2, Cell(0, 0)),
+            monomino(3, Cell(5, 0)),
+            monomino(4, Cell(5, 1)),
+        ];
+
+        assert_eq!(hint(&board, &placements), Hint::Misplaced(2));
+    }
+
+    #[test]
+    fn recognises_a_solved_board() {
+        let definition = bundled("0.intro/intro2.yaml");
+        let board = Board::from_definition(&definition);
+        let solution = Solver::new(&definition)
+            .first_solution()
+            .expect("Bundled puzzle has no solution");
+
+        assert_eq!(hint(&board, &solution), Hint::Solved);
+    }
+}
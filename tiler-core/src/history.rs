@@ -0,0 +1,99 @@
+/// An undo/redo stack. Recording a new entry forgets everything that was undone.
+#[derive(Debug, Clone)]
+pub struct History<T> {
+    done: Vec<T>,
+    undone: Vec<T>,
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        History {
+            done: vec![],
+            undone: vec![],
+        }
+    }
+}
+
+impl<T> History<T> {
+    pub fn record(&mut self, entry: T) {
+        self.done.push(entry);
+        self.undone.clear();
+    }
+
+    /// Returns the entry to roll back, if any
+    pub fn undo(&mut self) -> Option<&T> {
+        let entry = self.done.pop()?;
+        self.undone.push(entry);
+        self.undone.last()
+    }
+
+    /// Returns the entry to apply again, if any
+    pub fn redo(&mut self) -> Option<&T> {
+        let entry = self.undone.pop()?;
+        self.done.push(entry);
+        self.done.last()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records changing `state` to `to`, the way the game records moves
+    fn change(history: &mut History<(i32, i32)>, state: &mut i32, to: i32) {
+        history.record((*state, to));
+        *state = to;
+    }
+
+    #[test]
+    fn undo_and_redo_restore_earlier_states() {
+        let mut history = History::default();
+        let mut state = 0;
+        change(&mut history, &mut state, 1);
+        change(&mut history, &mut state, 2);
+
+        for expected in [1, 0] {
+            state = history.undo().unwrap().0;
+            assert_eq!(state, expected);
+        }
+        assert!(!history.can_undo());
+
+        for expected in [1, 2] {
+            state = history.redo().unwrap().1;
+            assert_eq!(state, expected);
+        }
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn recording_forgets_what_was_undone() {
+        let mut history = History::default();
+        let mut state = 0;
+        change(&mut history, &mut state, 1);
+        change(&mut history, &mut state, 2);
+        state = history.undo().unwrap().0;
+
+        change(&mut history, &mut state, 3);
+
+        assert!(!history.can_redo());
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.undo(), Some(&(1, 3)));
+    }
+
+    #[test]
+    fn undo_with_nothing_recorded_does_nothing() {
+        let mut history = History::<(i32, i32)>::default();
+
+        assert_eq!(history.undo(), None);
+        assert!(!history.can_undo());
+        assert!(!history.can_redo());
+    }
+}
@@ -0,0 +1,22 @@
+pub mod board;
+pub mod catalog;
+pub mod chapters;
+pub mod daily;
+pub mod design;
+pub mod difficulty;
+pub mod generator;
+pub mod grid;
+pub mod hints;
+pub mod history;
+pub mod lint;
+pub mod orientation;
+pub mod palette;
+pub mod puzzles;
+pub mod render;
+pub mod save;
+pub mod share;
+pub mod solver;
+pub mod span;
+
+#[cfg(test)]
+mod testing;
@@ -0,0 +1,247 @@
+use std::fmt;
+
+use crate::board::Cell;
+use crate::palette;
+use crate::puzzles::{Position, PuzzleDefinition};
+use crate::solver::Solver;
+
+/// A problem with a puzzle. Each one points at the line of the puzzle's file it's about: the
+/// palette or shape a color is on, the shape that's off the grid, or else the start of the
+/// puzzle. Puzzles which weren't read from a file have no lines.
+#[derive(Debug)]
+pub enum LintError {
+    Parse(serde_yaml::Error),
+    /// A color in the puzzle's palette or on one of its shapes which isn't a hex code or CSS
+    /// color name
+    BadColor {
+        color: String,
+        line: Option<usize>,
+    },
+    /// A shape fixed at a position where its tiles don't line up with the grid's cells
+    OffGrid {
+        shape_idx: usize,
+        line: Option<usize>,
+    },
+    NoPieces {
+        line: Option<usize>,
+    },
+    TileCountMismatch {
+        background: usize,
+        pieces: usize,
+        line: Option<usize>,
+    },
+    Unsolvable {
+        line: Option<usize>,
+    },
+    MultipleSolutions {
+        line: Option<usize>,
+    },
+}
+
+impl LintError {
+    /// Returns the line of the puzzle file this error points at, if any
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            LintError::Parse(err) => err.location().map(|location| location.line()),
+            LintError::BadColor { line, .. }
+            | LintError::OffGrid { line, .. }
+            | LintError::NoPieces { line }
+            | LintError::TileCountMismatch { line, .. }
+            | LintError::Unsolvable { line }
+            | LintError::MultipleSolutions { line } => *line,
+        }
+    }
+}
+
+impl fmt::Display for LintError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LintError::Parse(err) => write!(f, "failed to parse puzzle: {err}"),
+            LintError::BadColor { color, .. } => {
+                write!(f, "`{color}` is not a hex code or CSS color name")
+            }
+            LintError::OffGrid { shape_idx, .. } => write!(
+                f,
+                "shape {shape_idx} is fixed where its tiles don't match the shape of the cells"
+            ),
+            LintError::NoPieces { .. } => write!(f, "the puzzle has no pieces to place"),
+            LintError::TileCountMismatch {
+                background, pieces, ..
+            } => write!(
+                f,
+                "the pieces have {pieces} tiles but the background has {background} cells to fill"
+            ),
+            LintError::Unsolvable { .. } => write!(f, "the puzzle has no solution"),
+            LintError::MultipleSolutions { .. } => write!(
+                f,
+                "the puzzle has more than one solution but `unique_solution` is set"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LintError {}
+
+/// Checks that a puzzle is playable: its colors parse, its fixed shapes sit on the grid, its
+/// pieces have as many tiles as the background and fit together in at least one way. Puzzles
+/// with `unique_solution` set must have exactly one way.
+///
+/// Puzzles allowing overlaps only need enough tiles to cover the background, since the solver
+/// can't search for overlapping solutions.
+pub fn lint(definition: &PuzzleDefinition) -> Result<(), LintError> {
+    let span = &definition.span;
+    let shape_colors = definition
+        .shapes
+        .iter()
+        .enumerate()
+        .filter_map(|(shape_idx, shape)| Some((shape.color.as_ref()?, span.shape(shape_idx))));
+    if let Some((color, line)) = definition
+        .palette
+        .iter()
+        .map(|color| (color, span.palette()))
+        .chain(shape_colors)
+        .find(|(color, _)| !palette::is_color(color))
+    {
+        return Err(LintError::BadColor {
+            color: color.clone(),
+            line,
+        });
+    }
+
+    if let Some(shape_idx) = definition.shapes.iter().position(|shape| {
+        !shape.interactable
+            && !definition
+                .grid
+                .is_translation(Cell::from(shape.pos.unwrap_or(Position(0, 0))))
+    }) {
+        return Err(LintError::OffGrid {
+            shape_idx,
+            line: span.shape(shape_idx),
+        });
+    }
+
+    // The rest are about the puzzle as a whole
+    let line = span.start;
+    let solver = Solver::new(definition);
+
+    let (background, pieces) = solver.tile_counts();
+    if pieces == 0 {
+        return Err(LintError::NoPieces { line });
+    }
+    if definition.allow_overlap {
+        return if pieces < background {
+            Err(LintError::TileCountMismatch {
+                background,
+                pieces,
+                line,
+            })
+        } else {
+            Ok(())
+        };
+    }
+
+    if background != pieces {
+        return Err(LintError::TileCountMismatch {
+            background,
+            pieces,
+            line,
+        });
+    }
+
+    match solver.limit(2).solve().count() {
+        0 => Err(LintError::Unsolvable { line }),
+        1 => Ok(()),
+        _ if definition.unique_solution => Err(LintError::MultipleSolutions { line }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzles::PuzzleFormat;
+    use crate::testing::bundled;
+
+    fn lint_yaml(yaml: &str) -> Result<(), LintError> {
+        PuzzleFormat::Yaml
+            .parse("test", yaml.to_string())
+            .map_err(LintError::Parse)
+            .and_then(|definition| lint(&definition))
+    }
+
+    #[test]
+    fn bundled_puzzles_pass() {
+        for path in [
+            "0.intro/intro0.yaml",
+            "1.hexagons/hex1.yaml",
+            "2.triangles/triangle1.yaml",
+            "999.complex/complex2.txt",
+        ] {
+            assert!(lint(&bundled(path)).is_ok(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn errors_point_at_lines() {
+        for (yaml, line) in [
+            ("name: Parse\nshapes:\n  - tiles: !Circle 3\n", Some(3)),
+            (
+                "name: Color\nshapes:\n  - interactable: false\n    tiles: !Rect [1, 1]\n  - tiles: !Rect [1, 1]\n    color: nope\n",
+                Some(5),
+            ),
+            (
+                "name: Palette\npalette:\n  - nope\nshapes:\n  - tiles: !Rect [1, 1]\n",
+                Some(2),
+            ),
+            (
+                "name: Off grid\ngrid: triangle\nshapes:\n  - interactable: false\n    tiles: !Rect [2, 1]\n\n  - interactable: false\n    pos: [1, 0]\n    tiles: !Rect [1, 1]\n",
+                Some(7),
+            ),
+            (
+                "\n# No pieces\nname: No pieces\nshapes:\n  - interactable: false\n    tiles: !Rect [1, 1]\n",
+                Some(3),
+            ),
+        ] {
+            let err = lint_yaml(yaml).unwrap_err();
+            assert_eq!(err.line(), line, "{}: {}", yaml, err);
+        }
+    }
+
+    #[test]
+    fn finds_each_kind_of_problem() {
+        let board = "name: Test\nshapes:\n  - interactable: false\n    tiles: !Rect [3, 1]\n";
+
+        assert!(matches!(
+            lint_yaml(&format!("{board}  - tiles: !Rect [1, 1]\n")),
+            Err(LintError::TileCountMismatch {
+                background: 3,
+                pieces: 1,
+                line: Some(1),
+            })
+        ));
+        assert!(matches!(
+            lint_yaml(&format!("{board}  - tiles: !Rect [1, 3]\n")),
+            Err(LintError::Unsolvable { line: Some(1) })
+        ));
+        assert!(matches!(
+            lint_yaml(&format!(
+                "unique_solution: true\n{board}  - tiles: !Rect [2, 1]\n  - tiles: !Rect [1, 1]\n"
+            )),
+            Err(LintError::MultipleSolutions { line: Some(1) })
+        ));
+        assert!(lint_yaml(&format!(
+            "{board}  - tiles: !Rect [2, 1]\n  - tiles: !Rect [1, 1]\n"
+        ))
+        .is_ok());
+    }
+
+    #[test]
+    fn puzzles_not_read_from_a_file_have_no_lines() {
+        let definition = PuzzleDefinition::from_ascii_art("Empty".to_string(), String::new());
+
+        assert!(matches!(
+            lint(&definition),
+            Err(LintError::NoPieces { line: None })
+        ));
+    }
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+use crate::grid::Grid;
+use crate::puzzles::Position;
+
+/// How a shape has been turned: mirrored horizontally first, then rotated clockwise by `turns`
+/// steps of the puzzle's [`Grid`]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Orientation {
+    #[serde(alias = "quarter_turns")]
+    pub turns: u8,
+    pub flipped: bool,
+}
+
+impl Orientation {
+    /// Returns every orientation a shape may be placed in
+    pub fn all(grid: Grid, allow_rotation: bool, allow_flip: bool) -> Vec<Orientation> {
+        let turns = if allow_rotation {
+            0..grid.turns()
+        } else {
+            0..1
+        };
+        let flipped: &[bool] = if allow_flip { &[false, true] } else { &[false] };
+
+        itertools::iproduct!(flipped.iter().copied(), turns)
+            .map(|(flipped, turns)| Orientation { turns, flipped })
+            .collect()
+    }
+
+    /// Rotates a further step clockwise, or counter-clockwise if `clockwise` is false
+    pub fn rotated(self, grid: Grid, clockwise: bool) -> Self {
+        let steps = grid.turns();
+
+        Orientation {
+            turns: (self.turns + if clockwise { 1 } else { steps - 1 }) % steps,
+            flipped: self.flipped,
+        }
+    }
+
+    /// Mirrors horizontally on top of the current orientation
+    pub fn mirrored(self, grid: Grid) -> Self {
+        let steps = grid.turns();
+
+        Orientation {
+            turns: (steps - self.turns % steps) % steps,
+            flipped: !self.flipped,
+        }
+    }
+
+    /// Transforms tile positions within their bounding box, so the top-left corner of the
+    /// bounding box stays put. The output is in the same order as `tiles`.
+    pub fn apply(self, grid: Grid, tiles: &[Position]) -> Vec<Position> {
+        grid.orient(self, tiles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    /// Returns the tiles moved up against the top-left corner, so shapes which only differ by
+    /// where they are compare equal
+    fn normalised(grid: Grid, tiles: &[Position]) -> Vec<Position> {
+        let min_x = tiles.iter().map(|pos| pos.0).min().unwrap();
+        let min_y = tiles.iter().map(|pos| pos.1).min().unwrap();
+        let Position(x, y) = grid.translation_within(Position(min_x, min_y));
+
+        let mut tiles = tiles
+            .iter()
+            .map(|pos| Position(pos.0 - x, pos.1 - y))
+            .collect::<Vec<_>>();
+        tiles.sort_by_key(|pos| (pos.1, pos.0));
+        tiles
+    }
+
+    fn distinct_orientations(grid: Grid, tiles: &[Position], allow_flip: bool) -> usize {
+        Orientation::all(grid, true, allow_flip)
+            .into_iter()
+            .map(|orientation| normalised(grid, &orientation.apply(grid, tiles)))
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    #[test]
+    fn counts_distinct_orientations() {
+        let l_tetromino = [
+            Position(0, 0),
+            Position(0, 1),
+            Position(0, 2),
+            Position(1, 2),
+        ];
+        let hex_hook = [
+            Position(0, 0),
+            Position(1, 0),
+            Position(2, 0),
+            Position(0, 1),
+        ];
+        let triangle_hook = [
+            Position(0, 0),
+            Position(1, 0),
+            Position(2, 0),
+            Position(3, 0),
+            Position(1, 1),
+        ];
+
+        for (grid, tiles, turned, turned_or_mirrored) in [
+            (Grid::Square, &l_tetromino[..], 4, 8),
+            (Grid::Square, &[Position(0, 0), Position(1, 0)][..], 2, 2),
+            (Grid::Hex, &hex_hook[..], 6, 12),
+            (Grid::Hex, &[Position(0, 0), Position(1, 0)][..], 3, 3),
+            (Grid::Triangle, &triangle_hook[..], 6, 12),
+        ] {
+            assert_eq!(
+                distinct_orientations(grid, tiles, false),
+                turned,
+                "{:?} {:?}",
+                grid,
+                tiles
+            );
+            assert_eq!(
+                distinct_orientations(grid, tiles, true),
+                turned_or_mirrored,
+                "{:?} {:?}",
+                grid,
+                tiles
+            );
+        }
+    }
+
+    #[test]
+    fn mirroring_twice_changes_nothing() {
+        for grid in [Grid::Square, Grid::Hex, Grid::Triangle] {
+            for orientation in Orientation::all(grid, true, true) {
+                assert_eq!(orientation.mirrored(grid).mirrored(grid), orientation);
+                assert_ne!(orientation.mirrored(grid), orientation);
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+/// The colors pieces are drawn in. Viridis and cividis run from dark to light as well as
+/// changing hue, so they can be told apart with the common kinds of colorblindness.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Palette {
+    /// Puzzles with their own colors use them in place of this
+    #[default]
+    Warm,
+    Viridis,
+    Cividis,
+    HighContrast,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 4] = [
+        Palette::Warm,
+        Palette::Viridis,
+        Palette::Cividis,
+        Palette::HighContrast,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Warm => "Warm",
+            Palette::Viridis => "Viridis",
+            Palette::Cividis => "Cividis",
+            Palette::HighContrast => "High contrast",
+        }
+    }
+}
+
+/// The colors of the high contrast palette, used in order. These are the Okabe-Ito colors,
+/// with white in place of black so they all stand out from the background.
+pub const HIGH_CONTRAST: [&str; 8] = [
+    "#e69f00", "#56b4e9", "#009e73", "#f0e442", "#0072b2", "#d55e00", "#cc79a7", "#ffffff",
+];
+
+/// Returns true if `color` is a hex code or CSS color name
+pub fn is_color(color: &str) -> bool {
+    csscolorparser::parse(color).is_ok()
+}
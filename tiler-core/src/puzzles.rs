@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+use std::ops::{Add, Sub};
+
+use serde::{Deserialize, Serialize};
+
+use crate::catalog::PACK_SUFFIX;
+use crate::grid::Grid;
+use crate::span::Span;
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Position(pub usize, pub usize);
+
+impl Add for Position {
+    type Output = Self;
+    fn add(self, rhs: Position) -> Self {
+        Position(self.0 + rhs.0, self.1 + rhs.1)
+    }
+}
+
+impl Sub for Position {
+    type Output = Self;
+    fn sub(self, rhs: Position) -> Self {
+        Position(self.0 - rhs.0, self.1 - rhs.1)
+    }
+}
+
+impl Position {
+    pub fn min(p1: Position, p2: Position) -> Position {
+        Position(usize::min(p1.0, p2.0), usize::min(p1.1, p2.1))
+    }
+
+    pub fn max(p1: Position, p2: Position) -> Position {
+        Position(usize::max(p1.0, p2.0), usize::max(p1.1, p2.1))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PuzzleDefinition {
+    pub name: String,
+    pub shapes: Vec<ShapeDefinition>,
+    /// The shape of the cells, which tile positions are given in
+    #[serde(default)]
+    pub grid: Grid,
+    /// Where the puzzle goes in its chapter. Puzzles without one go after those with one.
+    #[serde(default)]
+    pub order: Option<i32>,
+    /// Fail the build if this puzzle has more than one solution
+    #[serde(default)]
+    pub unique_solution: bool,
+    #[serde(default)]
+    pub allow_rotation: bool,
+    #[serde(default)]
+    pub allow_flip: bool,
+    /// Let pieces stack on top of each other, so the board only has to be covered
+    #[serde(default)]
+    pub allow_overlap: bool,
+    /// Colors for the pieces, as hex codes or CSS color names, used in order and repeated if
+    /// there are more pieces. Shapes fixed in place, like the board, don't use up a color. These
+    /// replace the warm palette, but not the others players can pick.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub palette: Vec<String>,
+    /// Where the puzzle was read from, so problems with it can point at a line
+    #[serde(skip)]
+    pub span: Span,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PuzzleFormat {
+    Yaml,
+    AsciiArt,
+}
+
+impl PuzzleFormat {
+    /// Splits a puzzle file name into its short name and format, or returns `None` if the file
+    /// isn't a puzzle. Pack files aren't puzzles, even though they're YAML.
+    pub fn from_file_name(file_name: &str) -> Option<(&str, PuzzleFormat)> {
+        if file_name.ends_with(PACK_SUFFIX) {
+            None
+        } else if let Some(shortname) = file_name.strip_suffix(".yaml") {
+            Some((shortname, PuzzleFormat::Yaml))
+        } else {
+            file_name
+                .strip_suffix(".txt")
+                .map(|shortname| (shortname, PuzzleFormat::AsciiArt))
+        }
+    }
+
+    pub fn parse(
+        self,
+        shortname: &str,
+        contents: String,
+    ) -> Result<PuzzleDefinition, serde_yaml::Error> {
+        match self {
+            PuzzleFormat::Yaml => {
+                serde_yaml::from_str(&contents).map(|definition| PuzzleDefinition {
+                    span: Span::from_yaml(&contents),
+                    ..definition
+                })
+            }
+            PuzzleFormat::AsciiArt => Ok(PuzzleDefinition {
+                span: Span::from_ascii_art(&contents),
+                ..PuzzleDefinition::from_ascii_art(shortname.to_string(), contents)
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum Shape {
+    RawTiles(Vec<TileDefinition>),
+    Rect(usize, usize),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShapeDefinition {
+    #[serde(default)]
+    pub pos: Option<Position>,
+    #[serde(default = "bool_true")]
+    pub interactable: bool,
+    /// Overrides the puzzle's `allow_rotation` for this shape
+    #[serde(default)]
+    pub allow_rotation: Option<bool>,
+    /// Overrides the puzzle's `allow_flip` for this shape
+    #[serde(default)]
+    pub allow_flip: Option<bool>,
+    /// Color for the shape, as a hex code or CSS color name. Like the puzzle's `palette`, this
+    /// replaces the warm palette but not the others players can pick.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Name for the shape, like the letter of a pentomino. This is drawn on the shape in place of
+    /// a glyph, and single characters are kept when the puzzle is drawn as ASCII art.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    tiles: Shape,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TileType {
+    Foreground,
+    Background,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct TileDefinition {
+    pub pos: Position,
+    #[serde(default)]
+    pub tile_type: Option<TileType>,
+}
+
+impl TileType {
+    pub fn from_interactable(interactable: bool) -> Self {
+        if interactable {
+            TileType::Foreground
+        } else {
+            TileType::Background
+        }
+    }
+}
+
+fn bool_true() -> bool {
+    true
+}
+
+impl ShapeDefinition {
+    pub fn from_tiles(tiles: Vec<TileDefinition>, interactable: bool) -> Self {
+        ShapeDefinition {
+            tiles: Shape::RawTiles(tiles),
+            pos: None,
+            interactable,
+            allow_rotation: None,
+            allow_flip: None,
+            color: None,
+            label: None,
+        }
+    }
+
+    pub fn get_tiles(&self) -> Vec<TileDefinition> {
+        match self.tiles.clone() {
+            Shape::RawTiles(tiles) => tiles,
+            Shape::Rect(width, height) => itertools::iproduct!(0..width, 0..height)
+                .map(|(x, y)| TileDefinition {
+                    pos: Position(x, y),
+                    tile_type: None,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn can_rotate(&self, puzzle: &PuzzleDefinition) -> bool {
+        self.interactable && self.allow_rotation.unwrap_or(puzzle.allow_rotation)
+    }
+
+    pub fn can_flip(&self, puzzle: &PuzzleDefinition) -> bool {
+        self.interactable && self.allow_flip.unwrap_or(puzzle.allow_flip)
+    }
+}
+
+pub(crate) fn tiles_from_raw_positions(
+    grid: Grid,
+    raw_positions: &[Position],
+) -> Vec<TileDefinition> {
+    let tl_pos = match raw_positions.iter().copied().reduce(Position::min) {
+        Some(top_left) => grid.translation_within(top_left),
+        None => return vec![],
+    };
+
+    raw_positions
+        .iter()
+        .map(|raw_pos| *raw_pos - tl_pos)
+        .map(|pos| TileDefinition {
+            pos,
+            tile_type: None,
+        })
+        .collect()
+}
+
+impl PuzzleDefinition {
+    /// Reads a puzzle drawn with a character for each cell, where cells with the same character
+    /// make up a piece. Pieces are labelled with their character and come in character order.
+    pub fn from_ascii_art(name: String, art: String) -> Self {
+        let mut shapes: BTreeMap<char, Vec<Position>> = BTreeMap::new();
+        let mut raw_background_positions = vec![];
+        for (y, line) in art.lines().enumerate() {
+            for (x, chr) in line.chars().enumerate() {
+                if chr.is_whitespace() {
+                    continue;
+                }
+
+                shapes.entry(chr).or_default().push(Position(x, y));
+                raw_background_positions.push(Position(x, y));
+            }
+        }
+
+        let shapes = [ShapeDefinition::from_tiles(
+            tiles_from_raw_positions(Grid::Square, &raw_background_positions),
+            false,
+        )]
+        .into_iter()
+        .chain(shapes.into_iter().map(|(chr, raw_positions)| {
+            let tiles = tiles_from_raw_positions(Grid::Square, &raw_positions);
+            ShapeDefinition {
+                label: Some(chr.to_string()),
+                ..ShapeDefinition::from_tiles(tiles, true)
+            }
+        }))
+        .collect();
+
+        PuzzleDefinition {
+            name,
+            shapes,
+            grid: Grid::Square,
+            order: None,
+            unique_solution: false,
+            allow_rotation: false,
+            allow_flip: false,
+            allow_overlap: false,
+            palette: vec![],
+            span: Span::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_art_pieces_are_labelled_with_their_character() {
+        let puzzle =
+            PuzzleDefinition::from_ascii_art("Art".to_string(), "éé\"\n\\\\\"\n".to_string());
+
+        let labels = puzzle
+            .shapes
+            .iter()
+            .map(|shape_def| shape_def.label.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, [None, Some("\""), Some("\\"), Some("é")]);
+
+        let tiles = puzzle
+            .shapes
+            .iter()
+            .map(|shape_def| shape_def.get_tiles().len())
+            .collect::<Vec<_>>();
+        assert_eq!(tiles, [6, 2, 2, 2]);
+        assert_eq!(puzzle.shapes[3].get_tiles()[1].pos, Position(1, 0));
+    }
+}
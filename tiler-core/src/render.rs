@@ -0,0 +1,172 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::board::{Board, Cell, Placement};
+use crate::grid::Grid;
+use crate::puzzles::{PuzzleDefinition, TileType};
+use crate::solver::Solver;
+
+/// Characters used to tell pieces apart, in shape order
+const PIECE_LABELS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+/// How many pieces can be told apart by their label
+pub const MAX_PIECE_LABELS: usize = PIECE_LABELS.len();
+
+/// Draws the board as text with one character per cell: `.` for uncovered background, `#` for
+/// foreground tiles fixed in place and a letter for each placed piece. Pieces labelled with a
+/// character no other piece uses are drawn with it. Pieces hanging off the board are drawn too,
+/// and where pieces overlap the later one is shown.
+pub fn render(board: &Board, placements: &[Placement]) -> String {
+    let labels = piece_labels(board);
+    let mut cells: HashMap<Cell, char> = HashMap::new();
+
+    for shape in &board.shapes {
+        if let Some(offset) = shape.fixed_offset {
+            for (pos, tile_type) in &shape.tiles {
+                let cell = cells.entry(offset + Cell::from(*pos)).or_insert('.');
+                if *tile_type == TileType::Foreground {
+                    *cell = '#';
+                }
+            }
+        }
+    }
+
+    for placement in placements {
+        let shape = &board.shapes[placement.shape_idx];
+
+        for (pos, tile_type) in shape.oriented_tiles(board.grid, placement.orientation) {
+            if tile_type == TileType::Foreground {
+                cells.insert(
+                    placement.offset + Cell::from(pos),
+                    labels[&placement.shape_idx],
+                );
+            }
+        }
+    }
+
+    draw(&cells)
+}
+
+/// Draws a single piece the way it's defined, labelled as [`render`] labels it
+pub fn render_piece(board: &Board, shape_idx: usize) -> String {
+    let label = piece_labels(board)[&shape_idx];
+
+    let cells = board.shapes[shape_idx]
+        .tiles
+        .iter()
+        .filter(|(_, tile_type)| *tile_type == TileType::Foreground)
+        .map(|(pos, _)| (Cell::from(*pos), label))
+        .collect::<HashMap<Cell, char>>();
+
+    draw(&cells)
+}
+
+/// Draws a solution of the puzzle in the format read by [`PuzzleDefinition::from_ascii_art`].
+///
+/// Returns `None` if the puzzle has no solution or can't be described in that format: it has
+/// tiles fixed in place, pieces with background tiles, more pieces than there are labels or
+/// cells that aren't squares. Rotation, flip and overlap settings aren't part of the format, so
+/// they're dropped.
+pub fn to_ascii_art(definition: &PuzzleDefinition) -> Option<String> {
+    let board = Board::from_definition(definition);
+
+    let describable = board.shapes.iter().all(|shape| {
+        shape.tiles.iter().all(|(_, tile_type)| {
+            *tile_type == TileType::from_interactable(shape.fixed_offset.is_none())
+        })
+    });
+    let labels = piece_labels(&board);
+    let distinct_labels = labels.values().collect::<HashSet<_>>().len() == labels.len();
+    if !describable || definition.grid != Grid::Square || !distinct_labels {
+        return None;
+    }
+
+    let solution = Solver::from_board(&board).first_solution()?;
+    Some(render(&board, &solution))
+}
+
+/// Returns the label of each piece. Pieces keep their own label if it isn't whitespace, isn't
+/// used for anything else and no other piece has it. The rest are labelled in order with the
+/// characters left over, which repeat once they run out.
+fn piece_labels(board: &Board) -> HashMap<usize, char> {
+    let pieces = board
+        .shapes
+        .iter()
+        .enumerate()
+        .filter(|(_, shape)| shape.fixed_offset.is_none())
+        .collect::<Vec<_>>();
+
+    let own_label = |label: Option<char>| {
+        label.filter(|label| {
+            !label.is_whitespace()
+                && !matches!(label, '.' | '#')
+                && pieces
+                    .iter()
+                    .filter(|(_, shape)| shape.label == Some(*label))
+                    .count()
+                    == 1
+        })
+    };
+
+    let taken = pieces
+        .iter()
+        .filter_map(|(_, shape)| own_label(shape.label))
+        .collect::<HashSet<_>>();
+    let mut spare = (0..MAX_PIECE_LABELS)
+        .map(piece_label)
+        .filter(|label| !taken.contains(label))
+        .cycle();
+
+    pieces
+        .iter()
+        .map(|(shape_idx, shape)| {
+            let label = own_label(shape.label)
+                .or_else(|| spare.next())
+                .unwrap_or_else(|| piece_label(0));
+            (*shape_idx, label)
+        })
+        .collect()
+}
+
+/// Returns the label of the `piece`th interactable shape. Labels repeat after
+/// [`MAX_PIECE_LABELS`] pieces.
+pub fn piece_label(piece: usize) -> char {
+    char::from(PIECE_LABELS[piece % PIECE_LABELS.len()])
+}
+
+fn draw(cells: &HashMap<Cell, char>) -> String {
+    let (top_left, bottom_right) = match (
+        cells
+            .keys()
+            .copied()
+            .reduce(|c1, c2| Cell(c1.0.min(c2.0), c1.1.min(c2.1))),
+        cells
+            .keys()
+            .copied()
+            .reduce(|c1, c2| Cell(c1.0.max(c2.0), c1.1.max(c2.1))),
+    ) {
+        (Some(top_left), Some(bottom_right)) => (top_left, bottom_right),
+        _ => return String::new(),
+    };
+
+    (top_left.1..=bottom_right.1)
+        .map(|y| {
+            let line = (top_left.0..=bottom_right.0)
+                .map(|x| cells.get(&Cell(x, y)).copied().unwrap_or(' '))
+                .collect::<String>();
+
+            format!("{}\n", line.trim_end())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_art_round_trips() {
+        for art in ["AAB\nACC\n", "éé\nñé\n"] {
+            let definition = PuzzleDefinition::from_ascii_art("Art".to_string(), art.to_string());
+            assert_eq!(to_ascii_art(&definition).as_deref(), Some(art));
+        }
+    }
+}
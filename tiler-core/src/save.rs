@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::orientation::Orientation;
+use crate::palette::Palette;
+
+/// Everything remembered between sessions, keyed by puzzle short name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SaveData {
+    #[serde(default)]
+    pub puzzles: HashMap<String, PuzzleProgress>,
+    #[serde(default)]
+    pub settings: Settings,
+}
+
+/// Choices the player has made about how the game plays
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Settings {
+    /// Check the board every time a piece is dropped, instead of waiting to be asked
+    #[serde(default)]
+    pub auto_validate: bool,
+    #[serde(default)]
+    pub palette: Palette,
+    /// Draw each piece's label, or a glyph if it doesn't have one, so pieces can be told apart
+    /// without color
+    #[serde(default)]
+    pub glyphs: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PuzzleProgress {
+    #[serde(default)]
+    pub solved: bool,
+    /// Fastest solve, in seconds
+    #[serde(default)]
+    pub best_time: Option<f64>,
+    #[serde(default)]
+    pub best_moves: Option<usize>,
+    /// Fewest hints taken on the way to a solve
+    #[serde(default)]
+    pub fewest_hints: Option<usize>,
+
+    /// Seconds spent on the current attempt
+    #[serde(default)]
+    pub elapsed: f64,
+    /// Moves made in the current attempt
+    #[serde(default)]
+    pub moves: usize,
+    /// Hints taken during the current attempt
+    #[serde(default)]
+    pub hints: usize,
+    /// Where each shape was left, by shape index. Empty until the board has been touched.
+    #[serde(default)]
+    pub shapes: Vec<SavedShape>,
+}
+
+/// Where a shape was left, in snap steps of the puzzle's grid from the puzzle's origin
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedShape {
+    pub x: i32,
+    pub y: i32,
+    #[serde(default)]
+    pub orientation: Orientation,
+}
+
+impl SaveData {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Save data is always serializable")
+    }
+
+    pub fn progress(&self, shortname: &str) -> Option<&PuzzleProgress> {
+        self.puzzles.get(shortname)
+    }
+
+    pub fn progress_mut(&mut self, shortname: &str) -> &mut PuzzleProgress {
+        self.puzzles.entry(shortname.to_string()).or_default()
+    }
+
+    pub fn is_solved(&self, shortname: &str) -> bool {
+        matches!(self.progress(shortname), Some(progress) if progress.solved)
+    }
+}
+
+impl PuzzleProgress {
+    /// Marks the puzzle as solved by the current attempt, keeping the best time, move count and
+    /// hint count seen so far
+    pub fn record_solve(&mut self) {
+        self.solved = true;
+        self.best_time = Some(match self.best_time {
+            Some(best_time) => best_time.min(self.elapsed),
+            None => self.elapsed,
+        });
+        self.best_moves = Some(match self.best_moves {
+            Some(best_moves) => best_moves.min(self.moves),
+            None => self.moves,
+        });
+        self.fewest_hints = Some(match self.fewest_hints {
+            Some(fewest_hints) => fewest_hints.min(self.hints),
+            None => self.hints,
+        });
+    }
+}
@@ -0,0 +1,218 @@
+use std::fmt;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+use crate::orientation::Orientation;
+use crate::save::SavedShape;
+
+/// Bumped whenever the encoding changes, so old links can still be told apart
+const VERSION: u8 = 2;
+/// Orientations only had two bits for their turns before hex and triangle grids
+const VERSION_QUARTER_TURNS: u8 = 1;
+
+#[derive(Debug)]
+pub enum DecodeError {
+    Base64(base64::DecodeError),
+    UnsupportedVersion(u8),
+    Truncated,
+    InvalidOrientation(u8),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::Base64(err) => write!(f, "invalid board state: {err}"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported board state version {version}")
+            }
+            DecodeError::Truncated => write!(f, "board state ends part way through a shape"),
+            DecodeError::InvalidOrientation(orientation) => {
+                write!(f, "invalid shape orientation {orientation}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Encodes the layout of every shape into a string that's safe to put in a URL.
+///
+/// The string is base64 (URL-safe, unpadded) of a version byte followed by each shape's `x` and
+/// `y` as zigzag varints and its orientation as a single byte, with the flip above three bits of
+/// turns.
+pub fn encode_state(shapes: &[SavedShape]) -> String {
+    let mut bytes = vec![VERSION];
+
+    for shape in shapes {
+        write_varint(&mut bytes, shape.x);
+        write_varint(&mut bytes, shape.y);
+        bytes.push((shape.orientation.turns & 0b111) | (u8::from(shape.orientation.flipped) << 3));
+    }
+
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+pub fn decode_state(state: &str) -> Result<Vec<SavedShape>, DecodeError> {
+    let bytes = URL_SAFE_NO_PAD.decode(state).map_err(DecodeError::Base64)?;
+
+    let mut bytes = bytes.into_iter();
+    let turn_bits = match bytes.next() {
+        Some(VERSION) => 3,
+        Some(VERSION_QUARTER_TURNS) => 2,
+        Some(version) => return Err(DecodeError::UnsupportedVersion(version)),
+        None => return Err(DecodeError::Truncated),
+    };
+    let turn_mask = (1 << turn_bits) - 1;
+
+    let mut bytes = bytes.peekable();
+    let mut shapes = vec![];
+    while bytes.peek().is_some() {
+        let x = read_varint(&mut bytes)?;
+        let y = read_varint(&mut bytes)?;
+
+        let orientation = bytes.next().ok_or(DecodeError::Truncated)?;
+        if orientation >> (turn_bits + 1) != 0 {
+            return Err(DecodeError::InvalidOrientation(orientation));
+        }
+
+        shapes.push(SavedShape {
+            x,
+            y,
+            orientation: Orientation {
+                turns: orientation & turn_mask,
+                flipped: orientation >> turn_bits != 0,
+            },
+        });
+    }
+
+    Ok(shapes)
+}
+
+fn write_varint(bytes: &mut Vec<u8>, value: i32) {
+    // Zigzag so small negative offsets stay small
+    let mut value = ((value << 1) ^ (value >> 31)) as u32;
+
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &mut impl Iterator<Item = u8>) -> Result<i32, DecodeError> {
+    let mut value = 0u32;
+
+    for shift in (0..32).step_by(7) {
+        let byte = bytes.next().ok_or(DecodeError::Truncated)?;
+        value |= u32::from(byte & 0x7f) << shift;
+
+        if byte & 0x80 == 0 {
+            return Ok((value >> 1) as i32 ^ -((value & 1) as i32));
+        }
+    }
+
+    Err(DecodeError::Truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+
+    fn layout(grid: Grid) -> Vec<SavedShape> {
+        Orientation::all(grid, true, true)
+            .into_iter()
+            .enumerate()
+            .map(|(idx, orientation)| {
+                let idx = idx as i32;
+                SavedShape {
+                    x: idx * 37 - 100,
+                    y: 5000 - idx * idx * 311,
+                    orientation,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_every_grid() {
+        for grid in [Grid::Square, Grid::Hex, Grid::Triangle] {
+            let shapes = layout(grid);
+            assert_eq!(decode_state(&encode_state(&shapes)).unwrap(), shapes);
+        }
+    }
+
+    #[test]
+    fn round_trips_extremes() {
+        let shapes = [i32::MIN, -1, 0, 1, i32::MAX].map(|value| SavedShape {
+            x: value,
+            y: -value.saturating_add(1),
+            orientation: Orientation::default(),
+        });
+
+        assert_eq!(decode_state(&encode_state(&shapes)).unwrap(), shapes);
+        assert_eq!(decode_state(&encode_state(&[])).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn decodes_version_1() {
+        // Version 1, then (3, -2) turned three quarters and flipped, then (0, 0) only flipped
+        assert_eq!(
+            decode_state("AQYDBwAABA").unwrap(),
+            vec![
+                SavedShape {
+                    x: 3,
+                    y: -2,
+                    orientation: Orientation {
+                        turns: 3,
+                        flipped: true,
+                    },
+                },
+                SavedShape {
+                    x: 0,
+                    y: 0,
+                    orientation: Orientation {
+                        turns: 0,
+                        flipped: true,
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_states() {
+        let encoded = |bytes: &[u8]| URL_SAFE_NO_PAD.encode(bytes);
+
+        assert!(matches!(
+            decode_state("not a state!"),
+            Err(DecodeError::Base64(_))
+        ));
+        assert!(matches!(decode_state(""), Err(DecodeError::Truncated)));
+        assert!(matches!(
+            decode_state(&encoded(&[VERSION, 6])),
+            Err(DecodeError::Truncated)
+        ));
+        assert!(matches!(
+            decode_state(&encoded(&[VERSION, 6, 3])),
+            Err(DecodeError::Truncated)
+        ));
+        assert!(matches!(
+            decode_state(&encoded(&[VERSION, 0x80, 0x80, 0x80, 0x80, 0x80])),
+            Err(DecodeError::Truncated)
+        ));
+        assert!(matches!(
+            decode_state(&encoded(&[9, 0, 0, 0])),
+            Err(DecodeError::UnsupportedVersion(9))
+        ));
+        assert!(matches!(
+            decode_state(&encoded(&[VERSION_QUARTER_TURNS, 0, 0, 0b1000])),
+            Err(DecodeError::InvalidOrientation(0b1000))
+        ));
+        assert!(matches!(
+            decode_state(&encoded(&[VERSION, 0, 0, 0b1_0000])),
+            Err(DecodeError::InvalidOrientation(0b1_0000))
+        ));
+    }
+}
@@ -0,0 +1,525 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::board::{Board, Cell, Placement};
+use crate::grid::Grid;
+use crate::orientation::Orientation;
+use crate::puzzles::{Position, PuzzleDefinition, TileType};
+
+/// Finds placements of a puzzle's interactable shapes that exactly cover its background, using
+/// Knuth's Algorithm X with dancing links.
+///
+/// Every background tile must be covered by exactly one foreground tile and every interactable
+/// shape must be used, in any orientation the puzzle allows for it. Shapes with identical tiles
+/// are interchangeable, so solutions which only swap them around are counted once. Background
+/// tiles inside interactable shapes are ignored.
+#[derive(Debug, Clone)]
+pub struct Solver {
+    cells: Vec<Cell>,
+    pieces: Vec<Piece>,
+    candidates: Vec<Candidate>,
+    piece_tile_count: usize,
+    prefilled_fits: bool,
+    limit: Option<usize>,
+}
+
+/// The foreground tiles of a piece in each orientation it can be placed in
+type PieceOrientations = Vec<(Orientation, Vec<Position>)>;
+
+#[derive(Debug, Clone)]
+struct Piece {
+    /// The identical piece before this one. Copies of a piece are placed in order, so each
+    /// arrangement of them is only searched once.
+    prev_copy: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct Candidate {
+    piece: usize,
+    placement: Placement,
+    cells: Vec<Cell>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SolveResult {
+    /// The placements of each solution, ordered by shape index
+    pub solutions: Vec<Vec<Placement>>,
+    /// True if the search stopped at the solution limit, so there may be more solutions
+    pub capped: bool,
+    pub stats: SearchStats,
+}
+
+/// How much work a search took
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Pieces put down, including ones taken back again
+    pub nodes: usize,
+    /// Times the search had to choose how to fill a cell or place a piece
+    pub choice_points: usize,
+    /// Options there were to choose between, summed over every choice point
+    pub choices: usize,
+}
+
+impl SearchStats {
+    /// The average number of options at each choice point
+    pub fn branching_factor(&self) -> f64 {
+        if self.choice_points == 0 {
+            0.0
+        } else {
+            self.choices as f64 / self.choice_points as f64
+        }
+    }
+}
+
+impl SolveResult {
+    pub fn count(&self) -> usize {
+        self.solutions.len()
+    }
+}
+
+impl Solver {
+    pub fn new(definition: &PuzzleDefinition) -> Self {
+        Self::from_board(&Board::from_definition(definition))
+    }
+
+    pub fn from_board(board: &Board) -> Self {
+        let mut background = HashSet::new();
+        let mut prefilled = vec![];
+        let mut piece_tiles = vec![];
+
+        for (shape_idx, shape) in board.shapes.iter().enumerate() {
+            match shape.fixed_offset {
+                Some(offset) => {
+                    for (pos, tile_type) in &shape.tiles {
+                        let cell = offset + Cell::from(*pos);
+                        match tile_type {
+                            TileType::Background => background.insert(cell),
+                            TileType::Foreground => {
+                                prefilled.push(cell);
+                                false
+                            }
+                        };
+                    }
+                }
+                None => piece_tiles.push((
+                    shape_idx,
+                    shape
+                        .orientations
+                        .iter()
+                        .map(|orientation| {
+                            let tiles = shape
+                                .oriented_tiles(board.grid, *orientation)
+                                .into_iter()
+                                .filter(|(_, tile_type)| *tile_type == TileType::Foreground)
+                                .map(|(pos, _)| pos)
+                                .collect::<Vec<Position>>();
+
+                            (*orientation, tiles)
+                        })
+                        .collect::<PieceOrientations>(),
+                )),
+            }
+        }
+
+        // Foreground tiles of fixed shapes must sit on the background and fill it in
+        let prefilled_fits = prefilled.iter().all(|cell| background.remove(cell));
+
+        let mut cells = background.into_iter().collect::<Vec<Cell>>();
+        cells.sort();
+
+        let piece_tile_count = piece_tiles
+            .iter()
+            .filter_map(|(_, orientations)| orientations.first())
+            .map(|(_, tiles)| tiles.len())
+            .sum::<usize>();
+
+        let (pieces, candidates) = build_candidates(board.grid, &cells, &piece_tiles);
+
+        Solver {
+            cells,
+            pieces,
+            candidates,
+            piece_tile_count,
+            prefilled_fits,
+            limit: None,
+        }
+    }
+
+    /// Returns the number of background cells left to fill and the number of foreground tiles
+    /// across all pieces
+    pub fn tile_counts(&self) -> (usize, usize) {
+        (self.cells.len(), self.piece_tile_count)
+    }
+
+    /// Stops searching once `limit` solutions have been found
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn solve(&self) -> SolveResult {
+        self.solve_with_limit(self.limit)
+    }
+
+    pub fn first_solution(&self) -> Option<Vec<Placement>> {
+        self.solve_with_limit(Some(1)).solutions.into_iter().next()
+    }
+
+    fn solve_with_limit(&self, limit: Option<usize>) -> SolveResult {
+        let mut result = SolveResult::default();
+
+        if limit == Some(0) {
+            result.capped = true;
+            return result;
+        }
+
+        if !self.prefilled_fits || self.piece_tile_count != self.cells.len() {
+            return result;
+        }
+
+        let mut links = self.links();
+        let mut placed = vec![false; self.pieces.len()];
+        self.search(&mut links, &mut vec![], &mut placed, limit, &mut result);
+
+        result
+    }
+
+    /// Builds the exact cover matrix: one column per background cell followed by one column per
+    /// piece, and one row per candidate
+    fn links(&self) -> Links {
+        let cell_columns = self
+            .cells
+            .iter()
+            .enumerate()
+            .map(|(idx, cell)| (*cell, idx + 1))
+            .collect::<HashMap<Cell, usize>>();
+
+        let mut links = Links::new(self.cells.len() + self.pieces.len());
+        for (row, candidate) in self.candidates.iter().enumerate() {
+            let columns = candidate
+                .cells
+                .iter()
+                .map(|cell| cell_columns[cell])
+                .chain([self.cells.len() + 1 + candidate.piece])
+                .collect::<Vec<usize>>();
+
+            links.add_row(row, &columns);
+        }
+
+        links
+    }
+
+    /// Returns true once the search should stop
+    fn search(
+        &self,
+        links: &mut Links,
+        chosen: &mut Vec<usize>,
+        placed: &mut [bool],
+        limit: Option<usize>,
+        result: &mut SolveResult,
+    ) -> bool {
+        if links.right[ROOT] == ROOT {
+            let mut placements = chosen
+                .iter()
+                .map(|row| self.candidates[*row].placement)
+                .collect::<Vec<Placement>>();
+            placements.sort_by_key(|placement| placement.shape_idx);
+            result.solutions.push(placements);
+
+            result.capped = matches!(limit, Some(limit) if result.solutions.len() >= limit);
+            return result.capped;
+        }
+
+        // Copies of a piece all have the same number of candidates left, so the first one
+        // unplaced is picked ahead of the others whenever a piece column is the smallest
+        let column = links.smallest_column();
+        let choices = links
+            .rows(column)
+            .filter(|row| self.is_next_copy(&self.candidates[*row], placed))
+            .count();
+        if choices == 0 {
+            return false;
+        }
+        result.stats.choice_points += 1;
+        result.stats.choices += choices;
+
+        links.cover(column);
+
+        let mut stop = false;
+        let mut node = links.down[column];
+        while node != column && !stop {
+            let row = links.row[node];
+            let candidate = &self.candidates[row];
+
+            if self.is_next_copy(candidate, placed) {
+                result.stats.nodes += 1;
+                chosen.push(row);
+                placed[candidate.piece] = true;
+
+                let mut other = links.right[node];
+                while other != node {
+                    links.cover(links.column[other]);
+                    other = links.right[other];
+                }
+
+                stop = self.search(links, chosen, placed, limit, result);
+
+                let mut other = links.left[node];
+                while other != node {
+                    links.uncover(links.column[other]);
+                    other = links.left[other];
+                }
+
+                chosen.pop();
+                placed[candidate.piece] = false;
+            }
+
+            node = links.down[node];
+        }
+
+        links.uncover(column);
+
+        stop
+    }
+
+    /// Returns false if an identical piece before the one in `candidate` hasn't been placed yet
+    fn is_next_copy(&self, candidate: &Candidate, placed: &[bool]) -> bool {
+        match self.pieces[candidate.piece].prev_copy {
+            Some(prev) => placed[prev],
+            None => true,
+        }
+    }
+}
+
+fn build_candidates(
+    grid: Grid,
+    cells: &[Cell],
+    piece_tiles: &[(usize, PieceOrientations)],
+) -> (Vec<Piece>, Vec<Candidate>) {
+    let cell_set = cells.iter().copied().collect::<HashSet<Cell>>();
+
+    let mut pieces: Vec<Piece> = vec![];
+    let mut candidates = vec![];
+    let mut last_copies: HashMap<Vec<Vec<Cell>>, usize> = HashMap::new();
+
+    for (piece, (shape_idx, orientations)) in piece_tiles.iter().enumerate() {
+        let mut options = vec![];
+
+        for (orientation, tiles) in orientations {
+            let first_tile = match tiles.first() {
+                Some(first_tile) => first_tile,
+                None => continue,
+            };
+
+            for cell in cells {
+                let offset = *cell - Cell::from(*first_tile);
+                if !grid.is_translation(offset) {
+                    continue;
+                }
+
+                let mut covered = tiles
+                    .iter()
+                    .map(|pos| offset + Cell::from(*pos))
+                    .collect::<Vec<Cell>>();
+
+                if covered.iter().all(|cell| cell_set.contains(cell)) {
+                    covered.sort();
+                    options.push((
+                        Placement {
+                            shape_idx: *shape_idx,
+                            offset,
+                            orientation: *orientation,
+                        },
+                        covered,
+                    ));
+                }
+            }
+        }
+
+        options.sort_by(|(_, cells1), (_, cells2)| cells1.cmp(cells2));
+        options.dedup_by(|(_, cells1), (_, cells2)| cells1 == cells2);
+
+        let key = options
+            .iter()
+            .map(|(_, cells)| cells.clone())
+            .collect::<Vec<Vec<Cell>>>();
+
+        pieces.push(Piece {
+            prev_copy: last_copies.insert(key, piece),
+        });
+
+        candidates.extend(options.into_iter().map(|(placement, cells)| Candidate {
+            piece,
+            placement,
+            cells,
+        }));
+    }
+
+    (pieces, candidates)
+}
+
+const ROOT: usize = 0;
+
+/// Dancing links over a sparse 0/1 matrix. Node 0 is the root and nodes `1..=columns` are the
+/// column headers.
+struct Links {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    column: Vec<usize>,
+    row: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl Links {
+    fn new(columns: usize) -> Self {
+        let headers = columns + 1;
+
+        Links {
+            left: (0..headers).map(|idx| (idx + columns) % headers).collect(),
+            right: (0..headers).map(|idx| (idx + 1) % headers).collect(),
+            up: (0..headers).collect(),
+            down: (0..headers).collect(),
+            column: (0..headers).collect(),
+            row: vec![usize::MAX; headers],
+            size: vec![0; headers],
+        }
+    }
+
+    fn add_row(&mut self, row: usize, columns: &[usize]) {
+        let first = self.left.len();
+        let len = columns.len();
+
+        for (idx, column) in columns.iter().copied().enumerate() {
+            let node = first + idx;
+
+            self.left.push(first + (idx + len - 1) % len);
+            self.right.push(first + (idx + 1) % len);
+
+            self.up.push(self.up[column]);
+            self.down.push(column);
+            let above = self.up[column];
+            self.down[above] = node;
+            self.up[column] = node;
+
+            self.column.push(column);
+            self.row.push(row);
+            self.size[column] += 1;
+        }
+    }
+
+    /// Returns the rows with a node in `column`
+    fn rows(&self, column: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(Some(self.down[column]), move |node| Some(self.down[*node]))
+            .take_while(move |node| *node != column)
+            .map(move |node| self.row[node])
+    }
+
+    fn smallest_column(&self) -> usize {
+        let mut smallest = self.right[ROOT];
+
+        let mut column = self.right[smallest];
+        while column != ROOT {
+            if self.size[column] < self.size[smallest] {
+                smallest = column;
+            }
+            column = self.right[column];
+        }
+
+        smallest
+    }
+
+    fn cover(&mut self, column: usize) {
+        let (left, right) = (self.left[column], self.right[column]);
+        self.right[left] = right;
+        self.left[right] = left;
+
+        let mut row_node = self.down[column];
+        while row_node != column {
+            let mut node = self.right[row_node];
+            while node != row_node {
+                let (up, down) = (self.up[node], self.down[node]);
+                self.down[up] = down;
+                self.up[down] = up;
+                self.size[self.column[node]] -= 1;
+
+                node = self.right[node];
+            }
+            row_node = self.down[row_node];
+        }
+    }
+
+    fn uncover(&mut self, column: usize) {
+        let mut row_node = self.up[column];
+        while row_node != column {
+            let mut node = self.left[row_node];
+            while node != row_node {
+                let (up, down) = (self.up[node], self.down[node]);
+                self.down[up] = node;
+                self.up[down] = node;
+                self.size[self.column[node]] += 1;
+
+                node = self.left[node];
+            }
+            row_node = self.up[row_node];
+        }
+
+        let (left, right) = (self.left[column], self.right[column]);
+        self.right[left] = column;
+        self.left[right] = column;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::bundled;
+
+    #[test]
+    fn counts_solutions_of_bundled_puzzles() {
+        for (path, count) in [
+            ("0.intro/intro0.yaml", 1),
+            ("0.intro/intro1.yaml", 1),
+            ("0.intro/intro2.yaml", 1),
+            ("1.hexagons/hex1.yaml", 6),
+            ("2.triangles/triangle1.yaml", 6),
+            ("999.complex/complex1.yaml", 1),
+            ("999.complex/complex2.txt", 1),
+        ] {
+            assert_eq!(
+                Solver::new(&bundled(path)).solve().count(),
+                count,
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn solutions_are_valid() {
+        for path in [
+            "1.hexagons/hex1.yaml",
+            "2.triangles/triangle1.yaml",
+            "999.complex/complex2.txt",
+        ] {
+            let definition = bundled(path);
+            let board = Board::from_definition(&definition);
+
+            for solution in Solver::new(&definition).solve().solutions {
+                assert!(board.validate(&solution).is_valid(), "{}", path);
+            }
+        }
+    }
+
+    #[test]
+    fn stops_at_the_limit() {
+        // The domino and the monomino can go either way round
+        let definition = PuzzleDefinition::from_ascii_art("Limit".to_string(), "AAB\n".to_string());
+
+        assert_eq!(Solver::new(&definition).solve().count(), 2);
+
+        let result = Solver::new(&definition).limit(1).solve();
+        assert_eq!(result.count(), 1);
+        assert!(result.capped);
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Where a puzzle is written in the file it was read from, so problems with it can point at a
+/// line. Lines count from 1.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Span {
+    /// The line the puzzle starts on, or `None` if it wasn't read from a file
+    pub start: Option<usize>,
+    /// The line each shape starts on, in the same order as the shapes
+    pub shapes: Vec<usize>,
+    /// The line the puzzle's `palette` is on
+    pub palette: Option<usize>,
+}
+
+impl Span {
+    /// Returns the line shape `shape_idx` starts on, or the puzzle's first line if that isn't
+    /// known
+    pub(crate) fn shape(&self, shape_idx: usize) -> Option<usize> {
+        self.shapes.get(shape_idx).copied().or(self.start)
+    }
+
+    /// Returns the line of the puzzle's palette, or the puzzle's first line if that isn't known
+    pub(crate) fn palette(&self) -> Option<usize> {
+        self.palette.or(self.start)
+    }
+
+    /// Finds the parts of a puzzle written in YAML. YAML doesn't keep track of where values came
+    /// from, so this goes by the layout of the file, which has to be in block style like the
+    /// bundled puzzles. Only the first line is found for puzzles written some other way.
+    pub(crate) fn from_yaml(contents: &str) -> Self {
+        Self::from_yaml_lines(&numbered_lines(contents))
+    }
+
+    /// Finds the shapes of a puzzle drawn as ASCII art, which come in character order after the
+    /// board
+    pub(crate) fn from_ascii_art(art: &str) -> Self {
+        let mut first_lines = BTreeMap::new();
+        for (line, chrs) in numbered_lines(art) {
+            for chr in chrs.chars().filter(|chr| !chr.is_whitespace()) {
+                first_lines.entry(chr).or_insert(line);
+            }
+        }
+
+        Span {
+            start: Some(1),
+            shapes: [1].into_iter().chain(first_lines.into_values()).collect(),
+            palette: None,
+        }
+    }
+
+    /// Finds each puzzle in a pack file, in the same order as its `puzzles`. Puzzles given on
+    /// a single line, like most ASCII art, only have that line.
+    pub(crate) fn pack_from_yaml(contents: &str) -> Vec<Self> {
+        let lines = numbered_lines(contents);
+
+        items(&lines, "puzzles")
+            .into_iter()
+            .map(|item| Self::from_pack_item(&lines[item]).unwrap_or_default())
+            .collect()
+    }
+
+    /// Finds the puzzle under the `puzzle` key of an item in a pack file
+    fn from_pack_item(item: &[(usize, &str)]) -> Option<Self> {
+        let puzzle = item.iter().position(|(_, line)| has_key(line, "puzzle"))?;
+        let (line, text) = item[puzzle];
+        let nested = item[puzzle + 1..]
+            .iter()
+            .take_while(|(_, line)| is_blank(line) || indent(line) > indent(text))
+            .copied()
+            .collect::<Vec<_>>();
+
+        Some(if nested.iter().all(|(_, line)| is_blank(line)) {
+            Span {
+                start: Some(line),
+                ..Span::default()
+            }
+        } else {
+            Self::from_yaml_lines(&nested)
+        })
+    }
+
+    fn from_yaml_lines(lines: &[(usize, &str)]) -> Self {
+        Span {
+            start: lines
+                .iter()
+                .find(|(_, line)| !is_blank(line))
+                .map(|(line, _)| *line),
+            shapes: items(lines, "shapes")
+                .into_iter()
+                .map(|item| lines[item.start].0)
+                .collect(),
+            palette: top_level_key(lines, "palette").map(|idx| lines[idx].0),
+        }
+    }
+}
+
+fn numbered_lines(contents: &str) -> Vec<(usize, &str)> {
+    contents
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line))
+        .collect()
+}
+
+/// Returns the lines making up each item of the block sequence under the top level `key`
+fn items(lines: &[(usize, &str)], key: &str) -> Vec<Range<usize>> {
+    let key_idx = match top_level_key(lines, key) {
+        Some(key_idx) => key_idx,
+        None => return vec![],
+    };
+    let key_indent = indent(lines[key_idx].1);
+
+    let mut starts = vec![];
+    let mut end = lines.len();
+    let mut item_indent = None;
+    for (idx, (_, line)) in lines.iter().enumerate().skip(key_idx + 1) {
+        if is_blank(line) {
+            continue;
+        }
+
+        let is_item = line.trim_start().starts_with('-');
+        let item_indent = *item_indent.get_or_insert(indent(line));
+        if indent(line) < item_indent
+            || item_indent < key_indent
+            || (indent(line) == item_indent && !is_item)
+        {
+            end = idx;
+            break;
+        }
+        if indent(line) == item_indent {
+            starts.push(idx);
+        }
+    }
+
+    starts
+        .iter()
+        .zip(starts.iter().skip(1).chain([&end]))
+        .map(|(start, end)| *start..*end)
+        .collect()
+}
+
+/// Returns the index of the line with `key` at the indentation of the first line
+fn top_level_key(lines: &[(usize, &str)], key: &str) -> Option<usize> {
+    let top = lines
+        .iter()
+        .find(|(_, line)| !is_blank(line))
+        .map(|(_, line)| indent(line))?;
+
+    lines
+        .iter()
+        .position(|(_, line)| !is_blank(line) && indent(line) == top && has_key(line, key))
+}
+
+/// Returns true if `line` sets `key`, including as the first key of a sequence item
+fn has_key(line: &str, key: &str) -> bool {
+    matches!(
+        line.trim_start().trim_start_matches('-').trim_start().strip_prefix(key),
+        Some(rest) if rest.starts_with(':')
+    )
+}
+
+fn is_blank(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#') || line == "---"
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_shapes_in_yaml() {
+        let yaml = "# A comment\nname: Test\npalette: [red, blue]\nshapes:\n- interactable: false\n  tiles: !RawTiles\n    - pos: [0, 0]\n    - pos: [1, 0]\n\n- tiles: !Rect [1, 1]\nallow_flip: true\n";
+
+        assert_eq!(
+            Span::from_yaml(yaml),
+            Span {
+                start: Some(2),
+                shapes: vec![5, 10],
+                palette: Some(3),
+            }
+        );
+    }
+
+    #[test]
+    fn finds_puzzles_in_packs() {
+        let pack = "puzzles:\n  - shortname: art\n    puzzle: !AsciiArt \"AB\\n\"\n  - shortname: yaml\n    puzzle: !Yaml\n      name: Yaml\n      shapes:\n        - interactable: false\n          tiles: !Rect [1, 1]\n        - tiles: !Rect [1, 1]\n";
+
+        assert_eq!(
+            Span::pack_from_yaml(pack),
+            [
+                Span {
+                    start: Some(3),
+                    ..Span::default()
+                },
+                Span {
+                    start: Some(6),
+                    shapes: vec![8, 10],
+                    palette: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_shapes_in_ascii_art() {
+        assert_eq!(Span::from_ascii_art("AAB\nCCB\n").shapes, [1, 1, 1, 2]);
+    }
+}
@@ -0,0 +1,16 @@
+use crate::puzzles::{PuzzleDefinition, PuzzleFormat};
+
+/// Reads a puzzle bundled with the game, by its path within the game's puzzles directory
+pub(crate) fn bundled(path: &str) -> PuzzleDefinition {
+    let contents = std::fs::read_to_string(format!(
+        "{}/../game-gdnative/src/puzzles/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        path
+    ))
+    .expect("Failed to read bundled puzzle");
+    let (shortname, format) = PuzzleFormat::from_file_name(path).expect("Not a puzzle file");
+
+    format
+        .parse(shortname, contents)
+        .expect("Failed to parse bundled puzzle")
+}